@@ -0,0 +1,19 @@
+use rethdb_dexsync::server::start_server;
+use rethdb_dexsync::utils::init_db_read_only_from_env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    // Use the test db e.g. `RETH_DB_PATH=testdata/univ2-test-db` created by `create_univ2_test_db`
+    let provider_factory = init_db_read_only_from_env()?;
+
+    let addr: SocketAddr = "127.0.0.1:8545".parse()?;
+    let handle = start_server(addr, Arc::new(provider_factory)).await?;
+    println!("Serving dexsync JSON-RPC on http://{} and ws://{}", addr, addr);
+
+    handle.stopped().await;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use alloy_primitives::address;
+use rethdb_dexsync::univ2::read_pairs_reserves_history;
+use rethdb_dexsync::utils::init_db_read_only_from_env;
+use std::time::Instant;
+
+fn main() -> eyre::Result<()> {
+    let provider_factory = init_db_read_only_from_env()?;
+
+    // Read all reserve changes of the USDC/WETH pair in the block range
+    let now = Instant::now();
+    let usdc_weth = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+    let history = read_pairs_reserves_history(provider_factory.db_ref(), &[usdc_weth], 20_000_000..=20_100_000)?;
+    println!("Loaded reserves history in {:?} sec", now.elapsed());
+
+    for pair_history in history.iter() {
+        println!("Pair: {:#?}, Initial reserve: {:#?}", pair_history.address, pair_history.initial_reserve);
+        for (block_number, reserve) in pair_history.changes.iter().take(3) {
+            println!("Block: {}, Reserve: {:#?}", block_number, reserve);
+        }
+        println!("Total changes: {}", pair_history.changes.len());
+    }
+
+    Ok(())
+}
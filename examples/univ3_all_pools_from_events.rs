@@ -3,10 +3,9 @@ use rethdb_dexsync::utils::init_db_read_only_from_env;
 
 fn main() -> eyre::Result<()> {
     let factory = init_db_read_only_from_env()?;
-    let provider = factory.provider()?;
 
-    // Read all pools from UniswapV3Factory
-    let pools = read_univ3_pools(&provider)?;
+    // Read all pools from UniswapV3Factory, each block range is read with its own transaction
+    let pools = read_univ3_pools(factory.inner())?;
     for pool in pools.iter().take(3) {
         println!("Pool: {:#?}", pool);
     }
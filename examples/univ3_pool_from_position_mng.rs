@@ -1,4 +1,4 @@
-use reth_provider::StateProviderFactory;
+use alloy::eips::BlockNumberOrTag;
 use rethdb_dexsync::univ3::{UniV3PositionManager, UNI_V3_POSITION_MANAGER};
 use rethdb_dexsync::utils::init_db_read_only_from_env;
 
@@ -6,13 +6,13 @@ fn main() -> eyre::Result<()> {
     let factory = init_db_read_only_from_env()?;
 
     // Read all positions from PositionManager
-    let position_manager = UniV3PositionManager::load_pools(factory.latest()?, UNI_V3_POSITION_MANAGER)?;
+    let position_manager = UniV3PositionManager::load_pools(&factory, &BlockNumberOrTag::Latest, UNI_V3_POSITION_MANAGER)?;
     for (pool, slot0, liquidity) in position_manager.pools.iter().take(3) {
         println!("Pool: {:#?}", pool);
         println!("Slot0: {:#?}", slot0);
         println!("Liquidity: {:#?}", liquidity);
     }
-    println!("Total pools: {}", position_manager.pools.len());
+    println!("Total pools: {} at block {}", position_manager.pools.len(), position_manager.block.number);
 
     Ok(())
 }
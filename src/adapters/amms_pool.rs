@@ -0,0 +1,120 @@
+use crate::univ2::{UniV2Pair, UniV2PairReserve};
+use crate::univ3::{Univ3Pool, Univ3PoolState};
+use ::amms::amm::uniswap_v2::UniswapV2Pool;
+use ::amms::amm::uniswap_v3::{Info, UniswapV3Pool};
+use alloy_primitives::U256;
+use std::collections::HashMap;
+
+/// Fee of a Uniswap V2 pair in the unit of `amms` (0.3%)
+const UNI_V2_FEE: u32 = 300;
+
+/// Convert a pair with its reserves into an `amms` pool. The token decimals are not in the pair storage and must be
+/// provided as `(decimals0, decimals1)`.
+pub fn univ2_to_amms(pair: &UniV2Pair, reserve: &UniV2PairReserve, decimals: (u8, u8)) -> UniswapV2Pool {
+    UniswapV2Pool {
+        address: pair.address,
+        token_a: pair.token0,
+        token_a_decimals: decimals.0,
+        token_b: pair.token1,
+        token_b_decimals: decimals.1,
+        reserve_0: reserve.reserve0.to::<u128>(),
+        reserve_1: reserve.reserve1.to::<u128>(),
+        fee: UNI_V2_FEE,
+        ..Default::default()
+    }
+}
+
+/// Convert a pool with its state into an `amms` pool. The tick bitmap is rebuilt from the initialized ticks.
+pub fn univ3_to_amms(pool: &Univ3Pool, state: &Univ3PoolState, decimals: (u8, u8)) -> UniswapV3Pool {
+    let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+    let mut ticks = HashMap::new();
+    for (tick, info) in &state.ticks {
+        let compressed = tick.div_euclid(state.tick_spacing);
+        let word = tick_bitmap.entry((compressed >> 8) as i16).or_default();
+        *word |= U256::from(1) << (compressed & 0xff) as usize;
+        ticks.insert(*tick, Info::new(info.liquidity_gross.to::<u128>(), info.liquidity_net.as_i128(), info.initialized));
+    }
+
+    UniswapV3Pool {
+        address: pool.address,
+        token_a: pool.token0,
+        token_a_decimals: decimals.0,
+        token_b: pool.token1,
+        token_b_decimals: decimals.1,
+        liquidity: state.liquidity.to::<u128>(),
+        sqrt_price: U256::from(state.slot0.sqrt_price_x96),
+        fee: pool.fee.to::<u32>(),
+        tick: state.slot0.tick.as_i32(),
+        tick_spacing: state.tick_spacing,
+        tick_bitmap,
+        ticks,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ3::{TickInfo, Univ3Slot0};
+    use alloy_primitives::aliases::{I24, I56, U112, U24};
+    use alloy_primitives::{address, I128, U128, U16, U160};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_univ2_to_amms() {
+        let pair = UniV2Pair {
+            address: address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc"),
+            token0: address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+            token1: address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+            creation: None,
+        };
+        let reserve = UniV2PairReserve { block_timestamp_last: 1, reserve0: U112::from(2500), reserve1: U112::from(1) };
+        let amms_pool = univ2_to_amms(&pair, &reserve, (6, 18));
+        assert_eq!((amms_pool.token_a, amms_pool.token_a_decimals, amms_pool.reserve_0), (pair.token0, 6, 2500));
+        assert_eq!((amms_pool.token_b, amms_pool.token_b_decimals, amms_pool.reserve_1), (pair.token1, 18, 1));
+    }
+
+    #[test]
+    fn test_univ3_to_amms() {
+        let pool = Univ3Pool {
+            address: address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640"),
+            token0: address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+            token1: address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+            fee: U24::from(500),
+        };
+        let tick_info = |liquidity_net: i128| TickInfo {
+            liquidity_gross: U128::from(liquidity_net.unsigned_abs()),
+            liquidity_net: I128::try_from(liquidity_net).unwrap(),
+            fee_growth_outside_0x128: U256::ZERO,
+            fee_growth_outside_1x128: U256::ZERO,
+            tick_cumulative_outside: I56::ZERO,
+            seconds_per_liquidity_outside_x128: U160::ZERO,
+            seconds_outside: 0,
+            initialized: true,
+        };
+        let state = Univ3PoolState {
+            address: pool.address,
+            fee: pool.fee,
+            tick_spacing: 10,
+            slot0: Univ3Slot0 {
+                sqrt_price_x96: U160::from(1) << 96,
+                tick: I24::ZERO,
+                observation_index: U16::ZERO,
+                observation_cardinality: U16::from(1),
+                observation_cardinality_next: U16::from(1),
+                fee_protocol: 0,
+                unlocked: true,
+            },
+            liquidity: U128::from(100),
+            ticks: BTreeMap::from([(-2570, tick_info(100)), (2560, tick_info(-100))]),
+        };
+
+        let amms_pool = univ3_to_amms(&pool, &state, (6, 18));
+        assert_eq!((amms_pool.fee, amms_pool.tick, amms_pool.tick_spacing, amms_pool.liquidity), (500, 0, 10, 100));
+        assert_eq!(amms_pool.sqrt_price, U256::from(1) << 96);
+        assert_eq!(amms_pool.ticks[&-2570].liquidity_net, 100);
+        // tick 2560 is bit 0 of word 1, tick -2570 is bit 255 of word -2
+        assert_eq!(amms_pool.tick_bitmap[&1], U256::from(1));
+        assert_eq!(amms_pool.tick_bitmap[&-2], U256::from(1) << 255);
+    }
+}
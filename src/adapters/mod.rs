@@ -0,0 +1,3 @@
+mod amms_pool;
+
+pub use amms_pool::{univ2_to_amms, univ3_to_amms};
@@ -2,9 +2,10 @@ use alloy::eips::BlockNumberOrTag;
 use reth_db::cursor::DbCursorRW;
 use reth_db::tables;
 use reth_db::transaction::DbTxMut;
+use reth_primitives::StorageEntry;
 use rethdb_dexsync::test_utils::init_test_db_rw;
-use rethdb_dexsync::univ2::{PoolFilter, UniV2Factory, UNI_V2_FACTORY};
-use rethdb_dexsync::utils::{init_db_read_only_from_env, read_all_storage_entries};
+use rethdb_dexsync::univ2::{PoolFilter, UniV2Factory, PAIR_STATE_SLOTS, UNI_V2_FACTORY};
+use rethdb_dexsync::utils::{init_db_read_only_from_env, read_storage, state_provider};
 use std::path::{Path, PathBuf};
 
 #[tokio::main]
@@ -25,17 +26,16 @@ async fn main() -> eyre::Result<()> {
 
     let mut cursor = tx_rw.cursor_dup_write::<tables::PlainStorageState>()?;
 
-    let mut pair_addresses = vec![UNI_V2_FACTORY];
-    for (pair, _) in univ2_factory.pairs {
-        pair_addresses.push(pair.address);
+    // The known slots are read at the snapshot block of the pairs, the plain state may already be ahead
+    let snapshot = state_provider(&provider_factory, &BlockNumberOrTag::Number(univ2_factory.block.number))?;
+    let mut slots = univ2_factory.storage_slots().into_iter().map(|slot| (UNI_V2_FACTORY, slot)).collect::<Vec<_>>();
+    for (pair, _) in &univ2_factory.pairs {
+        slots.extend(PAIR_STATE_SLOTS.iter().map(|slot| (pair.address, *slot)));
     }
-    // An inconsistency can happen if a new pair was added after reading the pools
-    for address in pair_addresses {
-        let db_ref = provider_factory.db_ref();
-        let slots = read_all_storage_entries(db_ref.clone(), address)?;
-
-        for slot in slots {
-            cursor.upsert(address, slot)?
+    for (address, slot) in slots {
+        match read_storage(&snapshot, address, slot)? {
+            Some(value) if !value.is_zero() => cursor.upsert(address, StorageEntry::new(slot, value))?,
+            _ => {}
         }
     }
     test_db_provider.commit()?;
@@ -1,7 +1,7 @@
+use alloy::eips::BlockNumberOrTag;
 use reth_db::cursor::DbCursorRW;
 use reth_db::tables;
 use reth_db::transaction::DbTxMut;
-use reth_provider::StateProviderFactory;
 use rethdb_dexsync::test_utils::init_test_db_rw;
 use rethdb_dexsync::univ3::{UniV3PositionManager, UNI_V3_FACTORY, UNI_V3_POSITION_MANAGER};
 use rethdb_dexsync::utils::{init_db_read_only_from_env, read_all_storage_entries};
@@ -13,13 +13,13 @@ async fn main() -> eyre::Result<()> {
 
     /*
         let db_ref = factory.db_ref();
-        let slots = read_all_storage_entries(db_ref.clone(), address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640"))?;
+        let slots = read_all_storage_entries(db_ref, address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640"))?;
 
         println!("{:#?}", slots);
         println!("{:#?}", slots.len());
     */
 
-    let univ3_pos_mng = UniV3PositionManager::load_pools(factory.latest()?, UNI_V3_FACTORY)?;
+    let univ3_pos_mng = UniV3PositionManager::load_pools(&factory, &BlockNumberOrTag::Latest, UNI_V3_FACTORY)?;
 
     let test_db_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join("univ3-test-db");
     create_path_if_not_exists(&test_db_path)?;
@@ -40,7 +40,7 @@ async fn main() -> eyre::Result<()> {
     // An inconsistency can happen if a new pair was added after reading the pools
     for address in pool_addresses {
         let db_ref = factory.db_ref();
-        let slots = read_all_storage_entries(db_ref.clone(), address)?;
+        let slots = read_all_storage_entries(db_ref, address)?;
 
         for slot in slots {
             cursor.upsert(address, slot)?
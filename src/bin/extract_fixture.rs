@@ -0,0 +1,43 @@
+use alloy::eips::BlockNumberOrTag;
+use alloy_primitives::{Address, B256};
+use eyre::eyre;
+use rethdb_dexsync::test_utils::{Fixture, FixtureSpec};
+use rethdb_dexsync::utils::{init_db_read_only_from_env, resolve_block, state_provider};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Extract contracts at a block into a fixture for tests.
+///
+/// Usage: `extract_fixture <output> <block number|latest> <address[:slot,slot,..]>..` with the slots as 32 byte hex, all
+/// slots of a contract are extracted if none are given. E.g. the USDC/WETH pair with all slots:
+/// `extract_fixture testdata/usdc_weth.bincode 21000000 0xb4e16d0168e52d35cacd2c6185b44281ec28c9dc`
+fn main() -> eyre::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        return Err(eyre!("Usage: extract_fixture <output> <block number|latest> <address[:slot,slot,..]>.."));
+    }
+    let block_number_or_tag = match args[1].as_str() {
+        "latest" => BlockNumberOrTag::Latest,
+        number => BlockNumberOrTag::Number(number.parse()?),
+    };
+    let specs = args[2..].iter().map(|arg| parse_spec(arg)).collect::<eyre::Result<Vec<_>>>()?;
+
+    let provider_factory = init_db_read_only_from_env()?;
+    let block = resolve_block(&provider_factory, &block_number_or_tag)?;
+    let provider = state_provider(&provider_factory, &BlockNumberOrTag::Number(block.number))?;
+    let fixture = Fixture::extract(provider_factory.db_ref(), provider, block, &specs)?;
+
+    let slots: usize = fixture.accounts.iter().map(|account| account.storage.len()).sum();
+    println!("Extracted {} accounts with {} slots at block {}", fixture.accounts.len(), slots, block.number);
+    fixture.save(Path::new(&args[0]))?;
+
+    Ok(())
+}
+
+fn parse_spec(arg: &str) -> eyre::Result<FixtureSpec> {
+    let (address, slots) = match arg.split_once(':') {
+        Some((address, slots)) => (address, Some(slots.split(',').map(B256::from_str).collect::<Result<Vec<_>, _>>()?)),
+        None => (arg, None),
+    };
+    Ok(FixtureSpec { address: Address::from_str(address)?, slots })
+}
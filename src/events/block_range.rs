@@ -0,0 +1,44 @@
+use std::iter::StepBy;
+use std::ops::RangeInclusive;
+
+// CODE copied from reth: eth/filter.rs
+/// An iterator that yields _inclusive_ block ranges of a given step size
+#[derive(Debug)]
+pub struct BlockRangeInclusiveIter {
+    iter: StepBy<RangeInclusive<u64>>,
+    step: u64,
+    end: u64,
+}
+
+impl BlockRangeInclusiveIter {
+    pub fn new(range: RangeInclusive<u64>, step: u64) -> Self {
+        Self { end: *range.end(), iter: range.step_by(step as usize + 1), step }
+    }
+}
+
+impl Iterator for BlockRangeInclusiveIter {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.iter.next()?;
+        let end = (start + self.step).min(self.end);
+        if start > end {
+            return None;
+        }
+        Some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_range_iter() {
+        let ranges: Vec<_> = BlockRangeInclusiveIter::new(10..=35, 9).collect();
+        assert_eq!(ranges, vec![(10, 19), (20, 29), (30, 35)]);
+
+        let ranges: Vec<_> = BlockRangeInclusiveIter::new(5..=5, 9).collect();
+        assert_eq!(ranges, vec![(5, 5)]);
+    }
+}
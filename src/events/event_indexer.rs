@@ -0,0 +1,418 @@
+use crate::events::BlockRangeInclusiveIter;
+use crate::utils::{CacheError, DexSyncError};
+use alloy::rpc::types::{BlockNumHash, Filter, FilteredParams, Log};
+use alloy_primitives::{Address, LogData, B256};
+use alloy_sol_types::SolEvent;
+use reth_primitives::BlockHashOrNumber;
+use reth_provider::ProviderError;
+use reth_rpc_eth_types::logs_utils::{append_matching_block_logs, ProviderOrBlock};
+use reth_storage_api::BlockReader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// An event to index: the emitting contract, the event signature (topic0) and the first block to scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventSpec {
+    pub address: Address,
+    pub signature: B256,
+    pub start_block: u64,
+}
+
+impl EventSpec {
+    pub fn new(address: Address, signature: B256, start_block: u64) -> Self {
+        Self { address, signature, start_block }
+    }
+
+    /// Spec of an event declared with `sol!`.
+    pub fn of<E: SolEvent>(address: Address, start_block: u64) -> Self {
+        Self::new(address, E::SIGNATURE_HASH, start_block)
+    }
+}
+
+/// An event with its position in the chain. The raw log data is decoded with `decode`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedEvent<E> {
+    pub block: BlockNumHash,
+    pub block_timestamp: u64,
+    pub tx_hash: B256,
+    pub tx_index: u64,
+    pub log_index: u64,
+    pub address: Address,
+    pub event: E,
+}
+
+impl IndexedEvent<LogData> {
+    fn from_log(log: Log) -> Self {
+        Self {
+            block: BlockNumHash::new(log.block_number.unwrap_or_default(), log.block_hash.unwrap_or_default()),
+            block_timestamp: log.block_timestamp.unwrap_or_default(),
+            tx_hash: log.transaction_hash.unwrap_or_default(),
+            tx_index: log.transaction_index.unwrap_or_default(),
+            log_index: log.log_index.unwrap_or_default(),
+            address: log.inner.address,
+            event: log.inner.data,
+        }
+    }
+
+    /// Signature (topic0) of the event.
+    pub fn signature(&self) -> Option<B256> {
+        self.event.topics().first().copied()
+    }
+
+    /// Decode the log data as a `sol!` event.
+    pub fn decode<E: SolEvent>(&self) -> Result<IndexedEvent<E>, DexSyncError> {
+        let event = E::decode_log_data(&self.event, true).map_err(|e| DexSyncError::invalid_layout(self.address, e.to_string()))?;
+        Ok(IndexedEvent {
+            block: self.block,
+            block_timestamp: self.block_timestamp,
+            tx_hash: self.tx_hash,
+            tx_index: self.tx_index,
+            log_index: self.log_index,
+            address: self.address,
+            event,
+        })
+    }
+}
+
+/// Next block to scan for each event, persisted so that a restarted indexer resumes where it stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerCheckpoint {
+    pub next_blocks: BTreeMap<(Address, B256), u64>,
+}
+
+impl IndexerCheckpoint {
+    /// Load the checkpoint, an empty checkpoint if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, CacheError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(bincode::deserialize(&fs::read(path)?)?)
+    }
+
+    /// Save the checkpoint. The file is replaced atomically to survive a crash while writing.
+    pub fn save(&self, path: &Path) -> Result<(), CacheError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bincode::serialize(self)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Next block to scan for the spec.
+    pub fn next_block(&self, spec: &EventSpec) -> u64 {
+        self.next_blocks.get(&(spec.address, spec.signature)).copied().unwrap_or_default().max(spec.start_block)
+    }
+
+    fn advance(&mut self, specs: &[EventSpec], next_block: u64) {
+        for spec in specs {
+            let next = self.next_block(spec).max(next_block);
+            self.next_blocks.insert((spec.address, spec.signature), next);
+        }
+    }
+}
+
+/// Indexes the logs of multiple events. The blocks are scanned by header blooms and receipts in ranges, the ranges of a batch
+/// are read in parallel.
+#[derive(Clone, Debug)]
+pub struct EventIndexer {
+    specs: Vec<EventSpec>,
+    range_size: u64,
+    workers: usize,
+    checkpoint_path: Option<PathBuf>,
+}
+
+impl EventIndexer {
+    pub fn new(specs: Vec<EventSpec>) -> Self {
+        Self {
+            specs,
+            range_size: 10_000,
+            workers: std::thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+            checkpoint_path: None,
+        }
+    }
+
+    /// Number of blocks per range.
+    pub fn range_size(&mut self, range_size: u64) -> &mut Self {
+        self.range_size = range_size.max(1);
+        self
+    }
+
+    /// Number of ranges read in parallel.
+    pub fn workers(&mut self, workers: usize) -> &mut Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Persist the progress to the file and resume from it.
+    pub fn checkpoint_path(&mut self, path: PathBuf) -> &mut Self {
+        self.checkpoint_path = Some(path);
+        self
+    }
+
+    /// The persisted checkpoint, empty without a checkpoint path.
+    pub fn checkpoint(&self) -> Result<IndexerCheckpoint, CacheError> {
+        match &self.checkpoint_path {
+            Some(path) => IndexerCheckpoint::load(path),
+            None => Ok(IndexerCheckpoint::default()),
+        }
+    }
+
+    /// Index all events up to `to_block` and return them ordered by block and log index.
+    pub fn index_all<P: BlockReader + Sync>(&self, provider: &P, to_block: u64) -> eyre::Result<Vec<IndexedEvent<LogData>>> {
+        let mut events = vec![];
+        self.index(provider, to_block, |batch| {
+            events.extend(batch);
+            Ok(())
+        })?;
+        Ok(events)
+    }
+
+    /// Index the events up to `to_block` starting at the checkpoint. The events of each batch are passed ordered to
+    /// `on_batch`, the checkpoint is saved after `on_batch` succeeded. Returns the next block to scan.
+    pub fn index<P, F>(&self, provider: &P, to_block: u64, mut on_batch: F) -> eyre::Result<u64>
+    where
+        P: BlockReader + Sync,
+        F: FnMut(Vec<IndexedEvent<LogData>>) -> eyre::Result<()>,
+    {
+        let mut checkpoint = self.checkpoint()?;
+        let Some(from_block) = self.specs.iter().map(|spec| checkpoint.next_block(spec)).min() else {
+            return Ok(to_block + 1);
+        };
+        if from_block > to_block {
+            return Ok(from_block);
+        }
+
+        let filter = Filter::default()
+            .address(self.specs.iter().map(|spec| spec.address).collect::<Vec<_>>())
+            .event_signature(self.specs.iter().map(|spec| spec.signature).collect::<Vec<_>>());
+
+        let ranges: Vec<_> = BlockRangeInclusiveIter::new(from_block..=to_block, self.range_size - 1).collect();
+        let filter = &filter;
+        for batch in ranges.chunks(self.workers) {
+            let results: Vec<eyre::Result<Vec<IndexedEvent<LogData>>>> = std::thread::scope(|scope| {
+                let handles: Vec<_> =
+                    batch.iter().map(|&(from, to)| scope.spawn(move || read_range_logs(provider, filter, from, to))).collect();
+                handles.into_iter().map(|handle| handle.join().expect("range reader panicked")).collect()
+            });
+
+            let mut events = vec![];
+            for result in results {
+                // the filter matches every address with every signature, keep only the specs not yet scanned at the block
+                events.extend(result?.into_iter().filter(|event| {
+                    self.specs.iter().any(|spec| {
+                        spec.address == event.address
+                            && Some(spec.signature) == event.signature()
+                            && checkpoint.next_block(spec) <= event.block.number
+                    })
+                }));
+            }
+
+            let (batch_from, batch_to) = (batch[0].0, batch[batch.len() - 1].1);
+            debug!("Indexed blocks {} -> {}: {} events", batch_from, batch_to, events.len());
+            on_batch(events)?;
+
+            checkpoint.advance(&self.specs, batch_to + 1);
+            if let Some(path) = &self.checkpoint_path {
+                checkpoint.save(path)?;
+            }
+        }
+        Ok(to_block + 1)
+    }
+}
+
+// CODE adapted reth: eth/filter.rs
+fn read_range_logs<P: BlockReader>(provider: &P, filter: &Filter, from: u64, to: u64) -> eyre::Result<Vec<IndexedEvent<LogData>>> {
+    let filter_params = FilteredParams::new(Some(filter.clone()));
+    let address_filter = FilteredParams::address_filter(&filter.address);
+    let topics_filter = FilteredParams::topics_filter(&filter.topics);
+
+    let headers = provider.headers_range(from..=to).map_err(DexSyncError::Provider)?;
+    let mut logs = vec![];
+    for (idx, header) in headers.iter().enumerate() {
+        // only if filter matches
+        if !FilteredParams::matches_address(header.logs_bloom, &address_filter)
+            || !FilteredParams::matches_topics(header.logs_bloom, &topics_filter)
+        {
+            continue;
+        }
+        // these are consecutive headers, so we can use the parent hash of the next
+        // block to get the current header's hash
+        let block_hash = match headers.get(idx + 1) {
+            Some(parent) => parent.parent_hash,
+            None => provider
+                .block_hash(header.number)
+                .map_err(DexSyncError::Provider)?
+                .ok_or(DexSyncError::Provider(ProviderError::HeaderNotFound(header.number.into())))?,
+        };
+
+        if let Some(receipts) = provider.receipts_by_block(BlockHashOrNumber::from(block_hash)).map_err(DexSyncError::Provider)? {
+            append_matching_block_logs(
+                &mut logs,
+                ProviderOrBlock::Provider(provider),
+                &filter_params,
+                BlockNumHash::new(header.number, block_hash),
+                &receipts,
+                false,
+                header.timestamp,
+            )
+            .map_err(DexSyncError::Provider)?;
+        }
+    }
+    Ok(logs.into_iter().map(IndexedEvent::from_log).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, logs_bloom, U256};
+    use alloy_sol_types::{sol, SolValue};
+    use reth_db::models::StoredBlockBodyIndices;
+    use reth_db::tables;
+    use reth_db::transaction::DbTxMut;
+    use reth_primitives::{Header, Receipt};
+    use reth_stages::test_utils::TestStageDB;
+
+    sol! {
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+
+    #[test]
+    fn test_checkpoint() -> eyre::Result<()> {
+        let token = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let specs = vec![EventSpec::of::<Transfer>(token, 100), EventSpec::new(Address::with_last_byte(1), B256::with_last_byte(1), 500)];
+
+        let mut checkpoint = IndexerCheckpoint::default();
+        assert_eq!(checkpoint.next_block(&specs[0]), 100);
+
+        checkpoint.advance(&specs, 300);
+        assert_eq!(checkpoint.next_block(&specs[0]), 300);
+        assert_eq!(checkpoint.next_block(&specs[1]), 500);
+
+        let path = std::env::temp_dir().join(format!("dexsync_checkpoint_{}.bincode", std::process::id()));
+        checkpoint.save(&path)?;
+        assert_eq!(IndexerCheckpoint::load(&path)?, checkpoint);
+        fs::remove_file(&path)?;
+        assert_eq!(IndexerCheckpoint::load(&path)?, IndexerCheckpoint::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_event() -> eyre::Result<()> {
+        let token = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let (from, to) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let data =
+            LogData::new_unchecked(vec![Transfer::SIGNATURE_HASH, from.into_word(), to.into_word()], U256::from(42).abi_encode().into());
+        let event = IndexedEvent {
+            block: BlockNumHash::new(7, B256::with_last_byte(7)),
+            block_timestamp: 1_700_000_000,
+            tx_hash: B256::with_last_byte(3),
+            tx_index: 2,
+            log_index: 5,
+            address: token,
+            event: data,
+        };
+        assert_eq!(event.signature(), Some(Transfer::SIGNATURE_HASH));
+
+        let transfer = event.decode::<Transfer>()?;
+        assert_eq!((transfer.block.number, transfer.tx_index, transfer.log_index), (7, 2, 5));
+        assert_eq!((transfer.event.from, transfer.event.to, transfer.event.value), (from, to, U256::from(42)));
+
+        // wrong event
+        let mut wrong = event.clone();
+        wrong.event = LogData::new_unchecked(vec![B256::ZERO], Default::default());
+        assert!(matches!(wrong.decode::<Transfer>(), Err(DexSyncError::InvalidLayout { .. })));
+
+        Ok(())
+    }
+
+    fn transfer_log(token: Address, value: u64) -> alloy_primitives::Log {
+        let topics = vec![Transfer::SIGNATURE_HASH, Address::with_last_byte(1).into_word(), Address::with_last_byte(2).into_word()];
+        alloy_primitives::Log::new_unchecked(token, topics, U256::from(value).abi_encode().into())
+    }
+
+    /// Insert blocks `0..logs.len()` with one transaction per block and the logs in its receipt.
+    fn insert_blocks(test_db: &TestStageDB, logs: &[Vec<alloy_primitives::Log>]) -> eyre::Result<()> {
+        test_db.commit(|tx| {
+            for (number, block_logs) in logs.iter().enumerate() {
+                let number = number as u64;
+                let block_hash = B256::with_last_byte(number as u8 + 1);
+                let header = Header {
+                    number,
+                    parent_hash: if number == 0 { B256::ZERO } else { B256::with_last_byte(number as u8) },
+                    logs_bloom: logs_bloom(block_logs),
+                    timestamp: 1_700_000_000 + number * 12,
+                    ..Default::default()
+                };
+                tx.put::<tables::Headers>(number, header)?;
+                tx.put::<tables::CanonicalHeaders>(number, block_hash)?;
+                tx.put::<tables::HeaderNumbers>(block_hash, number)?;
+                tx.put::<tables::BlockBodyIndices>(number, StoredBlockBodyIndices { first_tx_num: number, tx_count: 1 })?;
+                tx.put::<tables::Receipts>(number, Receipt { success: true, logs: block_logs.clone(), ..Default::default() })?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_resume_from_checkpoint() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let token = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let other = Address::with_last_byte(9);
+        // transfers of the token in blocks 1, 3 and 5, block 2 only has a transfer of another token
+        insert_blocks(
+            &test_db,
+            &[
+                vec![],
+                vec![transfer_log(token, 1)],
+                vec![transfer_log(other, 2)],
+                vec![transfer_log(token, 3), transfer_log(token, 4)],
+                vec![],
+                vec![transfer_log(token, 5)],
+            ],
+        )?;
+
+        let path = std::env::temp_dir().join(format!("dexsync_indexer_checkpoint_{}.bincode", std::process::id()));
+        let mut indexer = EventIndexer::new(vec![EventSpec::of::<Transfer>(token, 0)]);
+        indexer.range_size(2).workers(1).checkpoint_path(path.clone());
+
+        // the first batch succeeds, the second fails before its checkpoint is saved
+        let mut events = vec![];
+        let mut batches = 0;
+        let result = indexer.index(&test_db.factory, 5, |batch| {
+            batches += 1;
+            if batches > 1 {
+                return Err(eyre::eyre!("STOPPED"));
+            }
+            events.extend(batch);
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(indexer.checkpoint()?.next_block(&indexer.specs[0]), 2);
+
+        // a restarted indexer resumes at the checkpoint
+        let next_block = indexer.index(&test_db.factory, 5, |batch| {
+            events.extend(batch);
+            Ok(())
+        })?;
+        assert_eq!(next_block, 6);
+        assert_eq!(indexer.checkpoint()?.next_block(&indexer.specs[0]), 6);
+        fs::remove_file(&path)?;
+
+        let values = events.iter().map(|event| event.decode::<Transfer>().map(|e| e.event.value)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(values, [1, 3, 4, 5].map(U256::from));
+        assert_eq!(events.iter().map(|event| event.block.number).collect::<Vec<_>>(), [1, 3, 3, 5]);
+        assert_eq!(events[1].block, BlockNumHash::new(3, B256::with_last_byte(4)));
+        assert_eq!(events[3].block_timestamp, 1_700_000_060);
+        assert_eq!(events, EventIndexer::new(vec![EventSpec::of::<Transfer>(token, 0)]).index_all(&test_db.factory, 5)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod block_range;
+mod event_indexer;
+
+pub use block_range::BlockRangeInclusiveIter;
+pub use event_indexer::{EventIndexer, EventSpec, IndexedEvent, IndexerCheckpoint};
@@ -0,0 +1,167 @@
+use crate::graph::liquidity_graph::{Edge, LiquidityGraph};
+use alloy_primitives::{Address, U256, U512};
+
+/// A cycle of swaps starting and ending at the same token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cycle {
+    pub edges: Vec<Edge>,
+    /// Product of the marginal rates, a cycle is profitable for small amounts if the rate is above 1
+    pub rate: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArbitrageOpportunity {
+    pub cycle: Cycle,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub profit: U256,
+}
+
+impl LiquidityGraph {
+    /// Enumerate all cycles from the base token with two up to `max_hops` swaps. Each pool and token is used at most once.
+    pub fn find_cycles(&self, base_token: Address, max_hops: usize) -> Vec<Cycle> {
+        let mut cycles = vec![];
+        let mut path = vec![];
+        self.visit(base_token, base_token, max_hops, &mut path, &mut cycles);
+        cycles
+    }
+
+    /// Find all profitable cycles from the base token and the input amount up to `max_amount_in` that maximizes the profit.
+    /// The result is sorted by profit, highest first.
+    pub fn find_arbitrage(&self, base_token: Address, max_hops: usize, max_amount_in: U256) -> Vec<ArbitrageOpportunity> {
+        let mut opportunities = vec![];
+        for cycle in self.find_cycles(base_token, max_hops) {
+            if cycle.rate <= 1.0 {
+                continue;
+            }
+            let (amount_in, amount_out) = self.optimal_amount_in(&cycle.edges, max_amount_in);
+            if amount_out <= amount_in {
+                continue;
+            }
+            opportunities.push(ArbitrageOpportunity { cycle, amount_in, amount_out, profit: amount_out - amount_in });
+        }
+        opportunities.sort_by_key(|opportunity| std::cmp::Reverse(opportunity.profit));
+        opportunities
+    }
+
+    /// Ternary search for the input amount with the highest profit. The profit of a swap path is concave in the input amount.
+    pub fn optimal_amount_in(&self, edges: &[Edge], max_amount_in: U256) -> (U256, U256) {
+        // A failed quote, e.g. an exhausted pool, counts as no output
+        let quote = |amount_in: U256| self.quote_path(edges, amount_in).unwrap_or_default();
+        // profit(a) > profit(b) without negative numbers, widened to not overflow near `U256::MAX`
+        let more_profitable = |a: (U256, U256), b: (U256, U256)| U512::from(a.1) + U512::from(b.0) > U512::from(b.1) + U512::from(a.0);
+
+        let (mut low, mut high) = (U256::ZERO, max_amount_in);
+        while high - low > U256::from(2) {
+            let third = (high - low) / U256::from(3);
+            let (mid_low, mid_high) = (low + third, high - third);
+            if more_profitable((mid_high, quote(mid_high)), (mid_low, quote(mid_low))) {
+                low = mid_low;
+            } else {
+                high = mid_high;
+            }
+        }
+
+        let mut best = (U256::ZERO, U256::ZERO);
+        // at most three amounts are left
+        for offset in 0..=(high - low).to::<u64>() {
+            let amount_in = low + U256::from(offset);
+            let candidate = (amount_in, quote(amount_in));
+            if more_profitable(candidate, best) {
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    fn visit(&self, base_token: Address, token: Address, max_hops: usize, path: &mut Vec<Edge>, cycles: &mut Vec<Cycle>) {
+        for edge in self.edges(&token) {
+            if edge.rate == 0.0 || path.iter().any(|hop| hop.pool == edge.pool) {
+                continue;
+            }
+            if edge.token_out == base_token {
+                if !path.is_empty() {
+                    let mut edges = path.clone();
+                    edges.push(edge.clone());
+                    let rate = edges.iter().map(|hop| hop.rate).product();
+                    cycles.push(Cycle { edges, rate });
+                }
+                continue;
+            }
+            if path.len() + 1 >= max_hops || path.iter().any(|hop| hop.token_out == edge.token_out) {
+                continue;
+            }
+            path.push(edge.clone());
+            self.visit(base_token, edge.token_out, max_hops, path, cycles);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ2::{UniV2Pair, UniV2PairReserve};
+    use alloy_primitives::address;
+    use alloy_primitives::aliases::U112;
+
+    const USDC: Address = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+    const WETH: Address = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+    const PAIR_A: Address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+    const PAIR_B: Address = address!("397ff1542f962076d0bfe58ea045ffa2d347aca0");
+
+    fn reserve(reserve_usdc: u128, reserve_weth: u128) -> UniV2PairReserve {
+        UniV2PairReserve { block_timestamp_last: 0, reserve0: U112::from(reserve_usdc), reserve1: U112::from(reserve_weth) }
+    }
+
+    fn test_graph(price_b: u128) -> LiquidityGraph {
+        // 2500 USDC/WETH in pair A and `price_b` USDC/WETH in pair B
+        let pair_a = UniV2Pair { address: PAIR_A, token0: USDC, token1: WETH, creation: None };
+        let pair_b = UniV2Pair { address: PAIR_B, token0: USDC, token1: WETH, creation: None };
+        let weth = 10u128.pow(18);
+        LiquidityGraph::from_pools(
+            &[(pair_a, reserve(2500 * 1000 * 10u128.pow(6), 1000 * weth)), (pair_b, reserve(price_b * 1000 * 10u128.pow(6), 1000 * weth))],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let graph = test_graph(2600);
+        let cycles = graph.find_cycles(WETH, 3);
+        // WETH -> USDC -> WETH over both pairs in both directions
+        assert_eq!(cycles.len(), 2);
+        assert!(cycles.iter().all(|cycle| cycle.edges.len() == 2));
+        assert_eq!(cycles.iter().filter(|cycle| cycle.rate > 1.0).count(), 1);
+    }
+
+    #[test]
+    fn test_find_arbitrage() {
+        let mut graph = test_graph(2600);
+        let max_amount_in = U256::from(100u128 * 10u128.pow(18));
+
+        let opportunities = graph.find_arbitrage(WETH, 3, max_amount_in);
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        // sell WETH for USDC in pair B and buy back in pair A
+        assert_eq!(opportunity.cycle.edges[0].pool, PAIR_B);
+        assert_eq!(opportunity.cycle.edges[1].pool, PAIR_A);
+        assert!(opportunity.profit > U256::ZERO);
+        assert!(opportunity.amount_in < max_amount_in);
+
+        // the optimum is better than its neighbours
+        let edges = &opportunity.cycle.edges;
+        for amount_in in [opportunity.amount_in / U256::from(2), opportunity.amount_in * U256::from(3) / U256::from(2)] {
+            let amount_out = graph.quote_path(edges, amount_in).unwrap();
+            assert!(amount_out < amount_in + opportunity.profit);
+        }
+
+        // the whole input range does not overflow
+        let (amount_in, amount_out) = graph.optimal_amount_in(edges, U256::MAX);
+        assert!(amount_out > amount_in);
+
+        // no arbitrage once the prices are equal
+        graph.update_univ2_reserve(PAIR_B, reserve(2500 * 1000 * 10u128.pow(6), 1000 * 10u128.pow(18))).unwrap();
+        assert!(graph.find_arbitrage(WETH, 3, max_amount_in).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::univ2::{UniV2Pair, UniV2PairReserve};
+use crate::univ3::{Univ3Pool, Univ3PoolState};
+use alloy_primitives::{Address, U256};
+use eyre::eyre;
+use std::collections::HashMap;
+
+const UNIV2_FEE: f64 = 0.003;
+const UNIV3_FEE_DENOMINATOR: f64 = 1_000_000.0;
+const Q96: f64 = 79228162514264337593543950336.0;
+
+/// State of a pool the edges of the graph are quoted with.
+#[derive(Debug)]
+pub enum GraphPoolState {
+    UniswapV2(UniV2PairReserve),
+    UniswapV3(Univ3PoolState),
+}
+
+#[derive(Debug)]
+pub struct GraphPool {
+    pub address: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub state: GraphPoolState,
+}
+
+impl GraphPool {
+    /// Output amount for an exact input swap using the exact math of the pool.
+    pub fn quote(&self, amount_in: U256, zero_for_one: bool) -> eyre::Result<U256> {
+        match &self.state {
+            GraphPoolState::UniswapV2(reserve) => Ok(reserve.get_amount_out(amount_in, zero_for_one)),
+            GraphPoolState::UniswapV3(pool_state) => pool_state.quote_exact_input(amount_in, zero_for_one),
+        }
+    }
+
+    /// Marginal amount of the output token for one unit of the input token after the fee. Zero if the pool has no liquidity.
+    pub fn marginal_rate(&self, zero_for_one: bool) -> f64 {
+        match &self.state {
+            GraphPoolState::UniswapV2(reserve) => {
+                let (reserve_in, reserve_out): (f64, f64) = if zero_for_one {
+                    (reserve.reserve0.into(), reserve.reserve1.into())
+                } else {
+                    (reserve.reserve1.into(), reserve.reserve0.into())
+                };
+                if reserve_in == 0.0 {
+                    return 0.0;
+                }
+                reserve_out / reserve_in * (1.0 - UNIV2_FEE)
+            }
+            GraphPoolState::UniswapV3(pool_state) => {
+                if pool_state.liquidity.is_zero() || pool_state.slot0.sqrt_price_x96.is_zero() {
+                    return 0.0;
+                }
+                let sqrt_price: f64 = f64::from(pool_state.slot0.sqrt_price_x96) / Q96;
+                let price = sqrt_price * sqrt_price;
+                let fee = 1.0 - pool_state.fee.to::<u32>() as f64 / UNIV3_FEE_DENOMINATOR;
+                if zero_for_one {
+                    price * fee
+                } else {
+                    fee / price
+                }
+            }
+        }
+    }
+}
+
+/// A swap direction of a pool from `token_in` to `token_out`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    pub pool: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub zero_for_one: bool,
+    /// Marginal rate of the pool after the fee
+    pub rate: f64,
+}
+
+/// Token graph with an edge for each swap direction of each pool.
+#[derive(Debug, Default)]
+pub struct LiquidityGraph {
+    pools: HashMap<Address, GraphPool>,
+    edges: HashMap<Address, Vec<Edge>>,
+}
+
+impl LiquidityGraph {
+    pub fn new() -> Self {
+        Self { pools: HashMap::new(), edges: HashMap::new() }
+    }
+
+    /// Build a graph from loaded pairs and pools. Uniswap V3 pools need their ticks for exact quoting.
+    pub fn from_pools(univ2_pairs: &[(UniV2Pair, UniV2PairReserve)], univ3_pools: Vec<(Univ3Pool, Univ3PoolState)>) -> Self {
+        let mut graph = Self::new();
+        for (pair, reserve) in univ2_pairs {
+            graph.add_univ2_pair(pair, reserve.clone());
+        }
+        for (pool, pool_state) in univ3_pools {
+            graph.add_univ3_pool(&pool, pool_state);
+        }
+        graph
+    }
+
+    pub fn add_univ2_pair(&mut self, pair: &UniV2Pair, reserve: UniV2PairReserve) {
+        self.add_pool(GraphPool {
+            address: pair.address,
+            token0: pair.token0,
+            token1: pair.token1,
+            state: GraphPoolState::UniswapV2(reserve),
+        });
+    }
+
+    pub fn add_univ3_pool(&mut self, pool: &Univ3Pool, pool_state: Univ3PoolState) {
+        self.add_pool(GraphPool {
+            address: pool.address,
+            token0: pool.token0,
+            token1: pool.token1,
+            state: GraphPoolState::UniswapV3(pool_state),
+        });
+    }
+
+    /// Replace the reserves of a known pair and update its edges.
+    pub fn update_univ2_reserve(&mut self, pair_address: Address, reserve: UniV2PairReserve) -> eyre::Result<()> {
+        match self.pools.get_mut(&pair_address) {
+            Some(GraphPool { state: state @ GraphPoolState::UniswapV2(_), .. }) => *state = GraphPoolState::UniswapV2(reserve),
+            _ => return Err(eyre!("UNIV2_PAIR_NOT_FOUND {:#?}", pair_address)),
+        }
+        self.update_edges(pair_address);
+        Ok(())
+    }
+
+    /// Replace the state of a known pool and update its edges.
+    pub fn update_univ3_state(&mut self, pool_address: Address, pool_state: Univ3PoolState) -> eyre::Result<()> {
+        match self.pools.get_mut(&pool_address) {
+            Some(GraphPool { state: state @ GraphPoolState::UniswapV3(_), .. }) => *state = GraphPoolState::UniswapV3(pool_state),
+            _ => return Err(eyre!("UNIV3_POOL_NOT_FOUND {:#?}", pool_address)),
+        }
+        self.update_edges(pool_address);
+        Ok(())
+    }
+
+    pub fn remove_pool(&mut self, pool_address: Address) -> Option<GraphPool> {
+        let pool = self.pools.remove(&pool_address)?;
+        for token in [pool.token0, pool.token1] {
+            if let Some(edges) = self.edges.get_mut(&token) {
+                edges.retain(|edge| edge.pool != pool_address);
+            }
+        }
+        Some(pool)
+    }
+
+    pub fn pool(&self, pool_address: &Address) -> Option<&GraphPool> {
+        self.pools.get(pool_address)
+    }
+
+    /// All edges starting at the token.
+    pub fn edges(&self, token: &Address) -> &[Edge] {
+        self.edges.get(token).map(|edges| edges.as_slice()).unwrap_or_default()
+    }
+
+    pub fn pools_len(&self) -> usize {
+        self.pools.len()
+    }
+
+    pub fn tokens_len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Output amount of an exact input swap along the edges. Each edge consumes the output of the previous one.
+    pub fn quote_path(&self, edges: &[Edge], amount_in: U256) -> eyre::Result<U256> {
+        let mut amount = amount_in;
+        for edge in edges {
+            let pool = self.pools.get(&edge.pool).ok_or_else(|| eyre!("POOL_NOT_FOUND {:#?}", edge.pool))?;
+            amount = pool.quote(amount, edge.zero_for_one)?;
+        }
+        Ok(amount)
+    }
+
+    fn add_pool(&mut self, pool: GraphPool) {
+        let address = pool.address;
+        if self.pools.contains_key(&address) {
+            self.remove_pool(address);
+        }
+        for (token_in, token_out, zero_for_one) in [(pool.token0, pool.token1, true), (pool.token1, pool.token0, false)] {
+            let edge = Edge { pool: address, token_in, token_out, zero_for_one, rate: pool.marginal_rate(zero_for_one) };
+            self.edges.entry(token_in).or_default().push(edge);
+        }
+        self.pools.insert(address, pool);
+    }
+
+    fn update_edges(&mut self, pool_address: Address) {
+        let Some(pool) = self.pools.get(&pool_address) else {
+            return;
+        };
+        for token in [pool.token0, pool.token1] {
+            for edge in self.edges.get_mut(&token).into_iter().flatten().filter(|edge| edge.pool == pool_address) {
+                edge.rate = pool.marginal_rate(edge.zero_for_one);
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod arbitrage;
+mod liquidity_graph;
+
+pub use arbitrage::{ArbitrageOpportunity, Cycle};
+pub use liquidity_graph::{Edge, GraphPool, GraphPoolState, LiquidityGraph};
@@ -2,5 +2,14 @@ pub mod univ2;
 pub mod univ3;
 pub mod utils;
 
+pub mod events;
+
+#[cfg(feature = "amms")]
+pub mod adapters;
 pub mod experimental;
+pub mod graph;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod state;
 pub mod test_utils;
+pub mod tokens;
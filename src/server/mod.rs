@@ -0,0 +1,5 @@
+mod rpc;
+mod types;
+
+pub use rpc::{start_server, DexSyncApiServer, DexSyncRpc};
+pub use types::{PairResponse, PoolResponse, PoolUpdate, TickResponse};
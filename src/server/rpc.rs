@@ -0,0 +1,449 @@
+use crate::server::types::{PairResponse, PoolResponse, PoolUpdate, TickResponse};
+use crate::univ2::{read_pair_if_exists, read_pair_reserves, read_pairs_interval, read_univ2_pairs_length};
+use crate::univ3::{
+    read_fee_amount_tick_spacing, read_liquidity, read_pool_key_by_address, read_pool_state, read_slot0, read_ticks, UNI_V3_FACTORY,
+    UNI_V3_POSITION_MANAGER,
+};
+use crate::utils::{state_provider, DexSyncError};
+use alloy::eips::BlockNumberOrTag;
+use alloy_primitives::aliases::U24;
+use alloy_primitives::{Address, BlockNumber, U256};
+use jsonrpsee::core::{async_trait, RpcResult, SubscriptionResult};
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::server::{Server, ServerHandle};
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use reth_provider::{StateProvider, StateProviderBox, StateProviderFactory};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+const MAX_PAIRS_PER_REQUEST: usize = 1000;
+const POOL_NOT_FOUND_CODE: i32 = -32001;
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[rpc(server, namespace = "dexsync")]
+pub trait DexSyncApi {
+    /// Uniswap V2 pairs of a factory with reserves starting at the pair index. At most 1000 pairs are returned.
+    #[method(name = "getPairs", blocking)]
+    fn get_pairs(&self, factory: Address, start: usize, limit: usize, block: Option<BlockNumberOrTag>) -> RpcResult<Vec<PairResponse>>;
+
+    /// Uniswap V2 pair or Uniswap V3 pool state.
+    #[method(name = "getPool", blocking)]
+    fn get_pool(&self, address: Address, block: Option<BlockNumberOrTag>) -> RpcResult<PoolResponse>;
+
+    /// All initialized ticks of a Uniswap V3 pool.
+    #[method(name = "getTicks", blocking)]
+    fn get_ticks(&self, address: Address, tick_spacing: i32, block: Option<BlockNumberOrTag>) -> RpcResult<Vec<TickResponse>>;
+
+    /// Output amount for an exact input swap. A given fee of a Uniswap V3 pool is used as is, without a fee it is read from
+    /// the position manager. The tick spacing of the fee is read from the factory.
+    #[method(name = "quote", blocking)]
+    fn quote(
+        &self,
+        address: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        fee: Option<U24>,
+        block: Option<BlockNumberOrTag>,
+    ) -> RpcResult<U256>;
+
+    /// Send the state of the pools on subscribe and every time it changes.
+    #[subscription(name = "subscribeUpdates" => "updates", unsubscribe = "unsubscribeUpdates", item = PoolUpdate)]
+    async fn subscribe_updates(&self, addresses: Vec<Address>) -> SubscriptionResult;
+}
+
+pub struct DexSyncRpc<P> {
+    provider_factory: Arc<P>,
+}
+
+impl<P: StateProviderFactory> DexSyncRpc<P> {
+    pub fn new(provider_factory: Arc<P>) -> Self {
+        Self { provider_factory }
+    }
+
+    fn state_provider(&self, block: Option<BlockNumberOrTag>) -> RpcResult<StateProviderBox> {
+        state_provider(self.provider_factory.as_ref(), &block.unwrap_or_default()).map_err(|e| match e {
+            DexSyncError::UnsupportedBlockTag(_) => invalid_params(e),
+            e => internal_error(e),
+        })
+    }
+}
+
+#[async_trait]
+impl<P: StateProviderFactory + 'static> DexSyncApiServer for DexSyncRpc<P> {
+    fn get_pairs(&self, factory: Address, start: usize, limit: usize, block: Option<BlockNumberOrTag>) -> RpcResult<Vec<PairResponse>> {
+        let provider = self.state_provider(block)?;
+        let pairs_length = read_univ2_pairs_length(&provider, factory).map_err(internal_error)?;
+        let end = pairs_length.min(start.saturating_add(limit.min(MAX_PAIRS_PER_REQUEST)));
+        if start >= end {
+            return Ok(vec![]);
+        }
+
+        let pairs = read_pairs_interval(&provider, factory, start, end).map_err(internal_error)?;
+        let mut result = Vec::with_capacity(pairs.len());
+        for (index, pair) in (start..end).zip(pairs) {
+            let reserve = read_pair_reserves(&provider, pair.address).map_err(internal_error)?;
+            result.push(PairResponse { index, pair, reserve });
+        }
+        Ok(result)
+    }
+
+    fn get_pool(&self, address: Address, block: Option<BlockNumberOrTag>) -> RpcResult<PoolResponse> {
+        let provider = self.state_provider(block)?;
+        read_pool(&provider, address).map_err(internal_error)?.ok_or_else(|| pool_not_found(address))
+    }
+
+    fn get_ticks(&self, address: Address, tick_spacing: i32, block: Option<BlockNumberOrTag>) -> RpcResult<Vec<TickResponse>> {
+        if tick_spacing <= 0 {
+            return Err(invalid_params("tick spacing must be positive"));
+        }
+        let provider = self.state_provider(block)?;
+        let ticks = read_ticks(&provider, address, tick_spacing).map_err(internal_error)?;
+        Ok(ticks.into_iter().map(|(tick, info)| TickResponse { tick, info }).collect())
+    }
+
+    fn quote(
+        &self,
+        address: Address,
+        zero_for_one: bool,
+        amount_in: U256,
+        fee: Option<U24>,
+        block: Option<BlockNumberOrTag>,
+    ) -> RpcResult<U256> {
+        let provider = self.state_provider(block)?;
+        if read_pair_if_exists(&provider, address).map_err(internal_error)?.is_some() {
+            let reserve = read_pair_reserves(&provider, address).map_err(internal_error)?;
+            return Ok(reserve.get_amount_out(amount_in, zero_for_one));
+        }
+
+        let fee = match fee {
+            Some(fee) => fee,
+            None => match read_pool_key_by_address(&provider, UNI_V3_POSITION_MANAGER, address).map_err(internal_error)? {
+                Some(pool_key) => pool_key.fee,
+                None if read_slot0(&provider, address).map_err(internal_error)?.is_none() => return Err(pool_not_found(address)),
+                None => return Err(invalid_params("fee of the pool is unknown, the pool is not registered in the position manager")),
+            },
+        };
+        let Some(tick_spacing) = read_fee_amount_tick_spacing(&provider, UNI_V3_FACTORY, fee).map_err(internal_error)? else {
+            return Err(invalid_params(format!("fee {} is not enabled in the factory", fee)));
+        };
+        let Some(pool_state) = read_pool_state(&provider, address, fee, tick_spacing).map_err(internal_error)? else {
+            return Err(pool_not_found(address));
+        };
+        pool_state.quote_exact_input(amount_in, zero_for_one).map_err(internal_error)
+    }
+
+    async fn subscribe_updates(&self, pending: PendingSubscriptionSink, addresses: Vec<Address>) -> SubscriptionResult {
+        let sink = pending.accept().await?;
+        let mut last_pools: HashMap<Address, PoolResponse> = HashMap::new();
+        let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = sink.closed() => break,
+                _ = interval.tick() => {}
+            }
+
+            // Reading from the database is blocking. A failed read is retried on the next tick.
+            let provider_factory = self.provider_factory.clone();
+            let addresses = addresses.clone();
+            let result = tokio::task::spawn_blocking(move || read_pools_at_best_block(provider_factory.as_ref(), &addresses)).await;
+            let (block_number, pools) = match result.map_err(eyre::Report::from).and_then(|result| result) {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Failed to read the pools of a subscription: {}", e);
+                    continue;
+                }
+            };
+
+            for pool in pools {
+                if last_pools.get(&pool.address()) == Some(&pool) {
+                    continue;
+                }
+                sink.send(SubscriptionMessage::from_json(&PoolUpdate { block_number, pool: pool.clone() })?).await?;
+                last_pools.insert(pool.address(), pool);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Start the JSON-RPC server. HTTP and WebSocket connections are served on the same address.
+pub async fn start_server<P: StateProviderFactory + 'static>(addr: SocketAddr, provider_factory: Arc<P>) -> eyre::Result<ServerHandle> {
+    let server = Server::builder().build(addr).await?;
+    Ok(server.start(DexSyncRpc::new(provider_factory).into_rpc()))
+}
+
+fn read_pool<T: StateProvider>(provider: T, address: Address) -> eyre::Result<Option<PoolResponse>> {
+    if let Some(pair) = read_pair_if_exists(&provider, address)? {
+        let reserve = read_pair_reserves(&provider, address)?;
+        return Ok(Some(PoolResponse::UniswapV2 { pair, reserve }));
+    }
+    match read_slot0(&provider, address)? {
+        None => Ok(None),
+        Some(slot0) => Ok(Some(PoolResponse::UniswapV3 { address, slot0, liquidity: read_liquidity(&provider, address)? })),
+    }
+}
+
+fn read_pools_at_best_block<P: StateProviderFactory>(
+    provider_factory: &P,
+    addresses: &[Address],
+) -> eyre::Result<(BlockNumber, Vec<PoolResponse>)> {
+    let block_number = provider_factory.best_block_number()?;
+    let provider = provider_factory.history_by_block_number(block_number)?;
+    let mut pools = vec![];
+    for address in addresses {
+        if let Some(pool) = read_pool(&provider, *address)? {
+            pools.push(pool);
+        }
+    }
+    Ok((block_number, pools))
+}
+
+fn internal_error<E: ToString>(e: E) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, e.to_string(), None::<()>)
+}
+
+fn invalid_params<E: ToString>(e: E) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>)
+}
+
+fn pool_not_found(address: Address) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(POOL_NOT_FOUND_CODE, format!("Pool not found: {:#?}", address), None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ2::{UniV2PairReserve, UNI_V2_FACTORY};
+    use crate::univ3::{swap_exact_input, TickInfo};
+    use alloy_primitives::aliases::{I24, U112, U80};
+    use alloy_primitives::{address, b256, keccak256, B256};
+    use alloy_sol_types::SolValue;
+    use reth_primitives::{Account, StorageEntry};
+    use reth_stages::test_utils::TestStageDB;
+    use std::collections::BTreeMap;
+
+    const PAIR_USDC_WETH: Address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+    const POOL_USDC_WETH: Address = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+    /// Copy of the USDC/WETH pool that is not registered in the position manager
+    const POOL_UNREGISTERED: Address = address!("8ad599c3a0ff1de082011efddc58f1908eb6e6d8");
+    const USDC: Address = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+    const WETH: Address = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+    /// Initialized tick of the pool above the current tick 198450
+    const POOL_TICK: i32 = 198460;
+
+    fn init_test_db() -> eyre::Result<TestStageDB> {
+        let test_db = TestStageDB::default();
+        let all_pairs_start_slot = keccak256(B256::with_last_byte(3).abi_encode());
+        // pool id 1 of the position manager with the key (USDC, WETH, 500)
+        let pool_key_slot = U256::from_be_slice(keccak256((U80::from(1), B256::with_last_byte(11)).abi_encode()).as_slice());
+        // tick 198460 is bit 134 of word 77 for a tick spacing of 10
+        let tick_slot = U256::from_be_slice(keccak256((I24::try_from(POOL_TICK)?, B256::with_last_byte(5)).abi_encode()).as_slice());
+        let pool_storage = vec![
+            StorageEntry::new(
+                B256::ZERO,
+                U256::from_be_slice(b256!("00010002d302d301800307320000000000004f96a4fc64ac43f93680a947bbda").as_slice()),
+            ),
+            StorageEntry::new(B256::with_last_byte(4), U256::from(183038598405746959u128)),
+            StorageEntry::new(keccak256((77i16, B256::with_last_byte(6)).abi_encode()), U256::from(1) << 134),
+            // liquidityNet 10^15 | liquidityGross 10^15
+            StorageEntry::new(B256::from(tick_slot), (U256::from(10u128.pow(15)) << 128) | U256::from(10u128.pow(15))),
+            StorageEntry::new(B256::from(tick_slot + U256::from(3)), U256::from(1) << 248),
+        ];
+        // `feeAmountTickSpacing` of the factory, fee 100 was enabled after the deployment
+        let fee_tick_spacing = |fee: u32, tick_spacing: u64| {
+            StorageEntry::new(keccak256((U256::from(fee), B256::with_last_byte(4)).abi_encode()), U256::from(tick_spacing))
+        };
+
+        test_db.insert_accounts_and_storages(vec![
+            (
+                UNI_V2_FACTORY,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(B256::with_last_byte(3), U256::from(1)),
+                        StorageEntry::new(all_pairs_start_slot, U256::from_be_slice(PAIR_USDC_WETH.as_slice())),
+                    ],
+                ),
+            ),
+            (
+                PAIR_USDC_WETH,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(B256::with_last_byte(6), U256::from_be_slice(USDC.as_slice())),
+                        StorageEntry::new(B256::with_last_byte(7), U256::from_be_slice(WETH.as_slice())),
+                        StorageEntry::new(
+                            B256::with_last_byte(8),
+                            U256::from_be_slice(b256!("6700f0af0000000003c5512b85fc28d1721e0000000000000000272e698defb8").as_slice()),
+                        ),
+                    ],
+                ),
+            ),
+            (POOL_USDC_WETH, (Account::default(), pool_storage.clone())),
+            (POOL_UNREGISTERED, (Account::default(), pool_storage)),
+            (UNI_V3_FACTORY, (Account::default(), vec![fee_tick_spacing(100, 1), fee_tick_spacing(500, 10), fee_tick_spacing(3000, 60)])),
+            (
+                UNI_V3_POSITION_MANAGER,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(keccak256((POOL_USDC_WETH, B256::with_last_byte(10)).abi_encode()), U256::from(1)),
+                        StorageEntry::new(B256::from(pool_key_slot), U256::from_be_slice(USDC.as_slice())),
+                        StorageEntry::new(
+                            B256::from(pool_key_slot + U256::from(1)),
+                            (U256::from(500) << 160) | U256::from_be_slice(WETH.as_slice()),
+                        ),
+                    ],
+                ),
+            ),
+        ])?;
+        Ok(test_db)
+    }
+
+    #[test]
+    fn test_get_pairs() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let rpc = DexSyncRpc::new(Arc::new(test_db.factory.clone()));
+
+        let pairs = rpc.get_pairs(UNI_V2_FACTORY, 0, 10, None)?;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].index, 0);
+        assert_eq!(pairs[0].pair.address, PAIR_USDC_WETH);
+
+        assert!(rpc.get_pairs(UNI_V2_FACTORY, 1, 10, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_pool() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let rpc = DexSyncRpc::new(Arc::new(test_db.factory.clone()));
+
+        let PoolResponse::UniswapV2 { pair, reserve } = rpc.get_pool(PAIR_USDC_WETH, None)? else {
+            panic!("Expected uniswap v2 pair");
+        };
+        assert_eq!(pair.address, PAIR_USDC_WETH);
+        assert_eq!(reserve.block_timestamp_last, 0x6700f0af);
+
+        let PoolResponse::UniswapV3 { slot0, liquidity, .. } = rpc.get_pool(POOL_USDC_WETH, None)? else {
+            panic!("Expected uniswap v3 pool");
+        };
+        assert_eq!(slot0.observation_cardinality.to::<u16>(), 723);
+        assert_eq!(liquidity.to::<u128>(), 183038598405746959u128);
+
+        let err = rpc.get_pool(Address::ZERO, None).unwrap_err();
+        assert_eq!(err.code(), POOL_NOT_FOUND_CODE);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let rpc = DexSyncRpc::new(Arc::new(test_db.factory.clone()));
+        let amount_in = U256::from(1_000_000_000u128);
+
+        let reserve = UniV2PairReserve {
+            block_timestamp_last: 0x6700f0af,
+            reserve0: U112::from(0x272e698defb8u64),
+            reserve1: U112::from(0x03c5512b85fc28d1721eu128),
+        };
+        assert_eq!(rpc.quote(PAIR_USDC_WETH, true, amount_in, None, None)?, reserve.get_amount_out(amount_in, true));
+        assert_eq!(rpc.quote(PAIR_USDC_WETH, false, amount_in, None, None)?, reserve.get_amount_out(amount_in, false));
+
+        // same result as the pool math with the initialized ticks of the pool
+        let ticks: BTreeMap<i32, TickInfo> =
+            rpc.get_ticks(POOL_USDC_WETH, 10, None)?.into_iter().map(|tick| (tick.tick, tick.info)).collect();
+        let sqrt_price_x96 = U256::from(1614245643731953243882325864332250u128);
+        for zero_for_one in [true, false] {
+            let expected = swap_exact_input(sqrt_price_x96, 198450, 183038598405746959, &ticks, 10, 500, amount_in, zero_for_one)?;
+            assert_eq!(rpc.quote(POOL_USDC_WETH, zero_for_one, amount_in, None, None)?, expected);
+            assert_eq!(rpc.quote(POOL_USDC_WETH, zero_for_one, amount_in, Some(U24::from(500)), None)?, expected);
+        }
+
+        // a fee that is not enabled in the factory
+        let err = rpc.quote(POOL_USDC_WETH, true, amount_in, Some(U24::from(10000)), None).unwrap_err();
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+
+        let err = rpc.quote(Address::ZERO, true, amount_in, None, None).unwrap_err();
+        assert_eq!(err.code(), POOL_NOT_FOUND_CODE);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_unregistered_pool() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let rpc = DexSyncRpc::new(Arc::new(test_db.factory.clone()));
+        let amount_in = U256::from(1_000_000_000u128);
+
+        // the fee is only known from the caller
+        let err = rpc.quote(POOL_UNREGISTERED, true, amount_in, None, None).unwrap_err();
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        for zero_for_one in [true, false] {
+            assert_eq!(
+                rpc.quote(POOL_UNREGISTERED, zero_for_one, amount_in, Some(U24::from(500)), None)?,
+                rpc.quote(POOL_USDC_WETH, zero_for_one, amount_in, None, None)?
+            );
+        }
+
+        // the tick spacing of a fee tier enabled later is read from the factory
+        assert!(rpc.quote(POOL_UNREGISTERED, true, amount_in, Some(U24::from(100)), None).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_ticks() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let rpc = DexSyncRpc::new(Arc::new(test_db.factory.clone()));
+
+        let ticks = rpc.get_ticks(POOL_USDC_WETH, 10, None)?;
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].tick, POOL_TICK);
+        assert_eq!(ticks[0].info.liquidity_gross.to::<u128>(), 10u128.pow(15));
+        assert!(ticks[0].info.initialized);
+
+        assert!(rpc.get_ticks(PAIR_USDC_WETH, 10, None)?.is_empty());
+        let err = rpc.get_ticks(POOL_USDC_WETH, 0, None).unwrap_err();
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_updates() -> eyre::Result<()> {
+        let test_db = init_test_db()?;
+        let module = DexSyncRpc::new(Arc::new(test_db.factory.clone())).into_rpc();
+
+        let mut subscription =
+            module.subscribe_unbounded("dexsync_subscribeUpdates", (vec![PAIR_USDC_WETH, Address::ZERO, POOL_USDC_WETH],)).await?;
+
+        // the state of all pools is sent on subscribe, unknown addresses are skipped
+        let (update, _) = subscription.next::<PoolUpdate>().await.unwrap()?;
+        assert_eq!(update.block_number, 0);
+        assert!(matches!(update.pool, PoolResponse::UniswapV2 { ref pair, .. } if pair.address == PAIR_USDC_WETH));
+        let (update, _) = subscription.next::<PoolUpdate>().await.unwrap()?;
+        let PoolResponse::UniswapV3 { address, liquidity, .. } = update.pool else {
+            panic!("Expected uniswap v3 pool");
+        };
+        assert_eq!((address, liquidity.to::<u128>()), (POOL_USDC_WETH, 183038598405746959u128));
+
+        // unchanged pools are not sent again
+        let next = tokio::time::timeout(UPDATE_INTERVAL * 2, subscription.next::<PoolUpdate>()).await;
+        assert!(next.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_method_names() {
+        let test_db = TestStageDB::default();
+        let module = DexSyncRpc::new(Arc::new(test_db.factory.clone())).into_rpc();
+        let method_names: Vec<&str> = module.method_names().collect();
+        for method_name in ["dexsync_getPairs", "dexsync_getPool", "dexsync_getTicks", "dexsync_quote", "dexsync_subscribeUpdates"] {
+            assert!(method_names.contains(&method_name), "missing {}", method_name);
+        }
+    }
+}
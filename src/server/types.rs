@@ -0,0 +1,39 @@
+use crate::univ2::{UniV2Pair, UniV2PairReserve};
+use crate::univ3::{TickInfo, Univ3Slot0};
+use alloy_primitives::{Address, BlockNumber, U128};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairResponse {
+    pub index: usize,
+    pub pair: UniV2Pair,
+    pub reserve: UniV2PairReserve,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PoolResponse {
+    UniswapV2 { pair: UniV2Pair, reserve: UniV2PairReserve },
+    UniswapV3 { address: Address, slot0: Univ3Slot0, liquidity: U128 },
+}
+
+impl PoolResponse {
+    pub fn address(&self) -> Address {
+        match self {
+            PoolResponse::UniswapV2 { pair, .. } => pair.address,
+            PoolResponse::UniswapV3 { address, .. } => *address,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickResponse {
+    pub tick: i32,
+    pub info: TickInfo,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolUpdate {
+    pub block_number: BlockNumber,
+    pub pool: PoolResponse,
+}
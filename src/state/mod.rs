@@ -0,0 +1,3 @@
+mod pool_state_store;
+
+pub use pool_state_store::{PoolStateStore, StateStoreError, StoredPoolState};
@@ -0,0 +1,179 @@
+use crate::univ2::UniV2PairReserve;
+use crate::univ3::Univ3Slot0;
+use alloy::eips::BlockNumHash;
+use alloy_primitives::{Address, B256, U128};
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StateStoreError {
+    #[error("Block not retained: {0}")]
+    UnknownBlock(B256),
+    #[error("Block {block} does not extend the tip {tip}")]
+    ParentMismatch { block: B256, tip: B256 },
+}
+
+/// State of a pool that changes with swaps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoredPoolState {
+    UniswapV2(UniV2PairReserve),
+    UniswapV3 { slot0: Univ3Slot0, liquidity: U128 },
+}
+
+/// An applied block with the states of the changed pools before the block.
+#[derive(Debug)]
+struct JournalEntry {
+    block: BlockNumHash,
+    previous: HashMap<Address, Option<StoredPoolState>>,
+}
+
+/// Latest pool states with a bounded journal of the applied blocks. A reorg is handled by reverting to the common ancestor
+/// and applying the blocks of the new fork, so the states never mix blocks of different forks.
+#[derive(Debug)]
+pub struct PoolStateStore {
+    states: HashMap<Address, StoredPoolState>,
+    journal: VecDeque<JournalEntry>,
+    /// Block before the oldest journal entry, the oldest block the state can be queried at
+    base: BlockNumHash,
+    max_depth: usize,
+}
+
+impl PoolStateStore {
+    /// Create the store from the states at the block, e.g. of a load. At most `max_depth` blocks are retained.
+    pub fn new(block: BlockNumHash, states: HashMap<Address, StoredPoolState>, max_depth: usize) -> Self {
+        Self { states, journal: VecDeque::new(), base: block, max_depth }
+    }
+
+    /// Block of the latest states.
+    pub fn tip(&self) -> BlockNumHash {
+        self.journal.back().map(|entry| entry.block).unwrap_or(self.base)
+    }
+
+    /// Retained blocks, oldest first.
+    pub fn blocks(&self) -> Vec<BlockNumHash> {
+        std::iter::once(self.base).chain(self.journal.iter().map(|entry| entry.block)).collect()
+    }
+
+    /// Latest state of the pool.
+    pub fn get(&self, address: &Address) -> Option<&StoredPoolState> {
+        self.states.get(address)
+    }
+
+    /// Apply the changed pool states of a block. The block must be a child of the tip.
+    pub fn apply_block<I>(&mut self, block: BlockNumHash, parent_hash: B256, changes: I) -> Result<(), StateStoreError>
+    where
+        I: IntoIterator<Item = (Address, StoredPoolState)>,
+    {
+        let tip = self.tip();
+        if parent_hash != tip.hash {
+            return Err(StateStoreError::ParentMismatch { block: block.hash, tip: tip.hash });
+        }
+
+        let mut previous = HashMap::new();
+        for (address, state) in changes {
+            let before = self.states.insert(address, state);
+            // keep the state before the first change if a pool changes twice in the block
+            previous.entry(address).or_insert(before);
+        }
+        self.journal.push_back(JournalEntry { block, previous });
+
+        while self.journal.len() > self.max_depth {
+            if let Some(entry) = self.journal.pop_front() {
+                self.base = entry.block;
+            }
+        }
+        Ok(())
+    }
+
+    /// Revert all blocks after the block. Returns the reverted blocks, newest first.
+    pub fn revert_to(&mut self, block_hash: B256) -> Result<Vec<BlockNumHash>, StateStoreError> {
+        let keep = self.retained_len(block_hash)?;
+        let mut reverted = vec![];
+        while self.journal.len() > keep {
+            let Some(entry) = self.journal.pop_back() else { break };
+            for (address, before) in entry.previous {
+                match before {
+                    Some(state) => self.states.insert(address, state),
+                    None => self.states.remove(&address),
+                };
+            }
+            reverted.push(entry.block);
+        }
+        Ok(reverted)
+    }
+
+    /// State of the pool after the retained block.
+    pub fn state_at(&self, block_hash: B256, address: &Address) -> Result<Option<StoredPoolState>, StateStoreError> {
+        let keep = self.retained_len(block_hash)?;
+        let mut state = self.states.get(address);
+        // undo the newer blocks, the oldest undone block holds the state at the block
+        for entry in self.journal.iter().skip(keep).rev() {
+            if let Some(before) = entry.previous.get(address) {
+                state = before.as_ref();
+            }
+        }
+        Ok(state.cloned())
+    }
+
+    /// Number of journal entries up to and including the block.
+    fn retained_len(&self, block_hash: B256) -> Result<usize, StateStoreError> {
+        if self.base.hash == block_hash {
+            return Ok(0);
+        }
+        match self.journal.iter().position(|entry| entry.block.hash == block_hash) {
+            Some(idx) => Ok(idx + 1),
+            None => Err(StateStoreError::UnknownBlock(block_hash)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::aliases::U112;
+
+    fn reserve(reserve0: u64) -> StoredPoolState {
+        StoredPoolState::UniswapV2(UniV2PairReserve { block_timestamp_last: 0, reserve0: U112::from(reserve0), reserve1: U112::from(1) })
+    }
+
+    fn block(number: u64, fork: u8) -> BlockNumHash {
+        BlockNumHash::new(number, B256::with_last_byte(number as u8 * 16 + fork))
+    }
+
+    #[test]
+    fn test_pool_state_store() {
+        let (pair_a, pair_b) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let mut store = PoolStateStore::new(block(1, 0), HashMap::from([(pair_a, reserve(100))]), 3);
+
+        store.apply_block(block(2, 0), block(1, 0).hash, [(pair_a, reserve(110)), (pair_b, reserve(5))]).unwrap();
+        store.apply_block(block(3, 0), block(2, 0).hash, [(pair_a, reserve(120)), (pair_a, reserve(130))]).unwrap();
+        assert_eq!(store.get(&pair_a), Some(&reserve(130)));
+        assert_eq!(store.state_at(block(1, 0).hash, &pair_a), Ok(Some(reserve(100))));
+        assert_eq!(store.state_at(block(1, 0).hash, &pair_b), Ok(None));
+        assert_eq!(store.state_at(block(2, 0).hash, &pair_a), Ok(Some(reserve(110))));
+
+        // a block of another fork must not be applied on top
+        assert_eq!(
+            store.apply_block(block(4, 1), block(3, 1).hash, []),
+            Err(StateStoreError::ParentMismatch { block: block(4, 1).hash, tip: block(3, 0).hash })
+        );
+
+        // reorg of block 3
+        assert_eq!(store.revert_to(block(2, 0).hash), Ok(vec![block(3, 0)]));
+        store.apply_block(block(3, 1), block(2, 0).hash, [(pair_b, reserve(6))]).unwrap();
+        assert_eq!(store.get(&pair_a), Some(&reserve(110)));
+        assert_eq!(store.get(&pair_b), Some(&reserve(6)));
+
+        // reverting to the base removes pools created later
+        assert_eq!(store.revert_to(block(1, 0).hash), Ok(vec![block(3, 1), block(2, 0)]));
+        assert_eq!(store.get(&pair_b), None);
+
+        // only the last blocks are retained
+        for number in 2..=5 {
+            store.apply_block(block(number, 0), block(number - 1, 0).hash, [(pair_a, reserve(number * 100))]).unwrap();
+        }
+        assert_eq!(store.blocks(), vec![block(2, 0), block(3, 0), block(4, 0), block(5, 0)]);
+        assert_eq!(store.state_at(block(2, 0).hash, &pair_a), Ok(Some(reserve(200))));
+        assert_eq!(store.revert_to(block(1, 0).hash), Err(StateStoreError::UnknownBlock(block(1, 0).hash)));
+    }
+}
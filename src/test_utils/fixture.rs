@@ -0,0 +1,198 @@
+use crate::utils::{read_all_storage_entries, DexSyncError};
+use alloy::eips::BlockNumHash;
+use alloy_primitives::{keccak256, Address, BlockNumber, Bytes, B256, U256};
+use eyre::eyre;
+use reth_db::cursor::DbCursorRO;
+use reth_db::models::storage_sharded_key::StorageShardedKey;
+use reth_db::transaction::{DbTx, DbTxMut};
+use reth_db::{tables, Database};
+use reth_primitives::{Account, Bytecode, StorageEntry};
+use reth_provider::StateProvider;
+use reth_stages::test_utils::TestStageDB;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Version of the fixture format, bumped on incompatible changes.
+pub const FIXTURE_VERSION: u32 = 1;
+
+/// A contract to extract with the slots to keep, all slots if `slots` is `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixtureSpec {
+    pub address: Address,
+    pub slots: Option<Vec<B256>>,
+}
+
+/// Account, bytecode and storage of a contract at the fixture block. Unset slots are omitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureAccount {
+    pub address: Address,
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Option<Bytes>,
+    pub storage: Vec<(B256, U256)>,
+}
+
+/// Compact state of some contracts at a block, extracted from a node to run tests against real state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub version: u32,
+    pub block_number: BlockNumber,
+    pub block_hash: B256,
+    pub accounts: Vec<FixtureAccount>,
+}
+
+impl Fixture {
+    /// Extract the contracts at the block of the provider. The slots of a contract without filter are the slots set in the
+    /// latest state or changed since the genesis according to the `StoragesHistory` index of the database.
+    pub fn extract<DB: Database, T: StateProvider>(db: &DB, provider: T, block: BlockNumHash, specs: &[FixtureSpec]) -> eyre::Result<Self> {
+        let mut accounts = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let Some(account) = provider.basic_account(spec.address)? else {
+                return Err(DexSyncError::MissingContract(spec.address).into());
+            };
+            let code = match account.bytecode_hash {
+                Some(code_hash) => provider.bytecode_by_hash(code_hash)?.map(|bytecode| bytecode.original_bytes()),
+                None => None,
+            };
+            let slots = match &spec.slots {
+                Some(slots) => slots.iter().copied().collect(),
+                None => read_known_slots(db, spec.address)?,
+            };
+            let mut storage = vec![];
+            for slot in slots {
+                match provider.storage(spec.address, slot)? {
+                    Some(value) if !value.is_zero() => storage.push((slot, value)),
+                    _ => {}
+                }
+            }
+            accounts.push(FixtureAccount { address: spec.address, nonce: account.nonce, balance: account.balance, code, storage });
+        }
+        Ok(Self { version: FIXTURE_VERSION, block_number: block.number, block_hash: block.hash, accounts })
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    /// Load a fixture, fails if it was written in another format version. The version is the leading `u32`, it is checked
+    /// before the body is decoded.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let encoded = fs::read(path)?;
+        let version: u32 = bincode::deserialize(&encoded)?;
+        if version != FIXTURE_VERSION {
+            return Err(eyre!("UNSUPPORTED_FIXTURE_VERSION {}", version));
+        }
+        Ok(bincode::deserialize(&encoded)?)
+    }
+
+    /// Write the accounts into the latest state of the test DB. The block hash is stored as the hash of block 0, the block
+    /// loaders resolve `Latest` to.
+    pub fn insert_into(&self, test_db: &TestStageDB) -> eyre::Result<()> {
+        let mut accounts = Vec::with_capacity(self.accounts.len());
+        let mut bytecodes = vec![];
+        for account in &self.accounts {
+            let code_hash = account.code.as_ref().map(keccak256);
+            if let (Some(code_hash), Some(code)) = (code_hash, &account.code) {
+                bytecodes.push((code_hash, Bytecode::new_raw(code.clone())));
+            }
+            let storage = account.storage.iter().map(|(slot, value)| StorageEntry::new(*slot, *value)).collect::<Vec<_>>();
+            accounts
+                .push((account.address, (Account { nonce: account.nonce, balance: account.balance, bytecode_hash: code_hash }, storage)));
+        }
+        test_db.insert_accounts_and_storages(accounts)?;
+        test_db.commit(|tx| {
+            for (code_hash, bytecode) in bytecodes {
+                tx.put::<tables::Bytecodes>(code_hash, bytecode)?;
+            }
+            tx.put::<tables::CanonicalHeaders>(0, self.block_hash)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+/// Slots of the contract in the latest plain state and in the storage history index.
+fn read_known_slots<DB: Database>(db: &DB, address: Address) -> Result<BTreeSet<B256>, DexSyncError> {
+    let mut slots: BTreeSet<B256> = read_all_storage_entries(db, address)?.into_iter().map(|entry| entry.key).collect();
+
+    let tx = db.tx()?;
+    let mut cursor = tx.cursor_read::<tables::StoragesHistory>()?;
+    for row in cursor.walk(Some(StorageShardedKey::new(address, B256::ZERO, 0)))? {
+        let (sharded_key, _) = row?;
+        if sharded_key.address != address {
+            break;
+        }
+        slots.insert(sharded_key.sharded_key.key);
+    }
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use reth_db::BlockNumberList;
+
+    #[test]
+    fn test_fixture() -> eyre::Result<()> {
+        let source_db = TestStageDB::default();
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let code = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+        let code_hash = keccak256(&code);
+        let slots = [B256::with_last_byte(6), B256::with_last_byte(7), B256::with_last_byte(8)];
+        source_db.insert_accounts_and_storages(vec![(
+            pair_address,
+            (
+                Account { nonce: 1, bytecode_hash: Some(code_hash), ..Default::default() },
+                slots.iter().map(|slot| StorageEntry::new(*slot, U256::from(slot[31]))).collect::<Vec<_>>(),
+            ),
+        )])?;
+        source_db.commit(|tx| {
+            tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code.clone()))?;
+            // a slot cleared after its last change
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pair_address, B256::with_last_byte(9), u64::MAX),
+                BlockNumberList::new_pre_sorted([1]),
+            )?;
+            Ok(())
+        })?;
+
+        let block = BlockNumHash::new(0, B256::with_last_byte(1));
+        let specs = [
+            FixtureSpec { address: pair_address, slots: None },
+            FixtureSpec { address: pair_address, slots: Some(vec![slots[2], B256::with_last_byte(10)]) },
+        ];
+        let fixture = Fixture::extract(source_db.factory.db_ref(), source_db.factory.latest()?, block, &specs)?;
+        assert_eq!(fixture.accounts[0].code, Some(code));
+        assert_eq!(fixture.accounts[0].storage.len(), 3);
+        assert_eq!(fixture.accounts[1].storage, vec![(slots[2], U256::from(8))]);
+
+        let missing = [FixtureSpec { address: Address::with_last_byte(1), slots: None }];
+        assert!(Fixture::extract(source_db.factory.db_ref(), source_db.factory.latest()?, block, &missing).is_err());
+
+        let path = std::env::temp_dir().join(format!("dexsync_fixture_{}.bincode", std::process::id()));
+        fixture.save(&path)?;
+        let loaded = Fixture::load(&path)?;
+        assert_eq!(loaded, fixture);
+
+        // a later version with another body
+        fs::write(&path, [2u32.to_le_bytes().as_slice(), &[0xff; 3]].concat())?;
+        assert_eq!(Fixture::load(&path).unwrap_err().to_string(), "UNSUPPORTED_FIXTURE_VERSION 2");
+        fs::remove_file(&path)?;
+
+        let test_db = TestStageDB::default();
+        Fixture { accounts: loaded.accounts[..1].to_vec(), ..loaded }.insert_into(&test_db)?;
+        let provider = test_db.factory.latest()?;
+        assert_eq!(provider.storage(pair_address, slots[1])?, Some(U256::from(7)));
+        assert_eq!(provider.bytecode_by_hash(code_hash)?.map(|bytecode| bytecode.original_bytes()), fixture.accounts[0].code);
+        assert_eq!(provider.basic_account(pair_address)?.map(|account| account.nonce), Some(1));
+
+        Ok(())
+    }
+}
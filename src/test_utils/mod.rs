@@ -1,3 +1,5 @@
+mod fixture;
 mod test_db_provider;
 
+pub use fixture::{Fixture, FixtureAccount, FixtureSpec, FIXTURE_VERSION};
 pub use test_db_provider::init_test_db_rw;
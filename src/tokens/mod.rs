@@ -0,0 +1,3 @@
+mod token_behavior;
+
+pub use token_behavior::{classify_token, TokenBehavior, TokenBehaviorCache};
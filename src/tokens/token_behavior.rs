@@ -0,0 +1,324 @@
+use crate::utils::{CacheError, DexSyncCache, DexSyncError};
+use alloy_primitives::{address, Address, Bytes, U256, U512};
+use alloy_sol_types::{sol, SolCall};
+use eyre::eyre;
+use reth_primitives::Header;
+use reth_provider::StateProvider;
+use reth_revm::database::StateProviderDatabase;
+use reth_revm::db::CacheDB;
+use reth_revm::primitives::{BlockEnv, TxKind};
+use reth_revm::Evm;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+sol! {
+    interface IERC20 {
+        function balanceOf(address owner) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function paused() external view returns (bool);
+    }
+}
+
+/// Receiver of the simulated transfers, an address without code or balance
+const PROBE: Address = address!("1000000000000000000000000000000000000001");
+const GAS_LIMIT: u64 = 1_000_000;
+/// Time to wait for a rebase
+const REBASE_DELAY: u64 = 86400;
+
+/// Behavior of a token observed by simulating a transfer out of a pair and back. A fee is in basis points.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenBehavior {
+    /// Fee of the transfer out of the pair (buy)
+    pub buy_fee_bps: u32,
+    /// Fee of the transfer back into the pair (sell)
+    pub sell_fee_bps: u32,
+    /// The balance of the probe changed a day later without a transfer, e.g. a time-based rebase. Rebases triggered by a
+    /// call or an oracle update are not detected
+    pub rebasing: bool,
+    /// The transfer out of the pair reverted and the token reports `paused()`
+    pub paused: bool,
+    /// A transfer reverted while the token is not paused, e.g. the pair or the receiver is blacklisted
+    pub blacklisting: bool,
+}
+
+impl TokenBehavior {
+    pub fn is_standard(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Classified tokens of a factory, cached next to the pool snapshot.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenBehaviorCache {
+    pub tokens: HashMap<Address, TokenBehavior>,
+}
+
+impl TokenBehaviorCache {
+    /// Load the cache of the factory, an empty cache if none was saved.
+    pub fn load(path: &Path, factory_address: Address) -> Result<Self, CacheError> {
+        match DexSyncCache::load_named(path, &Self::file_name(factory_address)) {
+            Err(CacheError::FileNotFound) => Ok(Self::default()),
+            result => result,
+        }
+    }
+
+    pub fn save(&self, path: &Path, factory_address: Address) -> eyre::Result<()> {
+        DexSyncCache::save_named(path, &Self::file_name(factory_address), self)
+    }
+
+    /// Classify the token in the pair, cached tokens are not simulated again.
+    pub fn classify<T: StateProvider>(
+        &mut self,
+        provider: T,
+        token: Address,
+        pair_address: Address,
+        header: &Header,
+    ) -> eyre::Result<&TokenBehavior> {
+        if !self.tokens.contains_key(&token) {
+            let behavior = classify_token(provider, token, pair_address, header)?;
+            self.tokens.insert(token, behavior);
+        }
+        Ok(&self.tokens[&token])
+    }
+
+    fn file_name(factory_address: Address) -> String {
+        format!("tokens_{:#?}", factory_address)
+    }
+}
+
+/// Classify a token by simulating a transfer of 1% of the pair balance to a probe address and back at the state of the
+/// provider. The calls run in the block of the header, a rebase is detected by reading the balance a day later.
+pub fn classify_token<T: StateProvider>(
+    provider: T,
+    token: Address,
+    pair_address: Address,
+    header: &Header,
+) -> eyre::Result<TokenBehavior> {
+    let block = BlockEnv {
+        number: U256::from(header.number),
+        coinbase: header.beneficiary,
+        timestamp: U256::from(header.timestamp),
+        basefee: U256::from(header.base_fee_per_gas.unwrap_or_default()),
+        prevrandao: Some(header.mix_hash),
+        ..Default::default()
+    };
+    let mut evm = TokenSimulator { db: CacheDB::new(StateProviderDatabase::new(provider)), token, block };
+    let mut behavior = TokenBehavior::default();
+
+    let pair_balance = evm.balance_of(pair_address)?;
+    if pair_balance.is_zero() {
+        return Err(DexSyncError::InvalidPool { address: pair_address, reason: format!("no balance of {:#?}", token) }.into());
+    }
+    let amount = (pair_balance / U256::from(100)).max(U256::from(1));
+
+    // buy: the pair sends the tokens to the probe
+    let probe_balance = evm.balance_of(PROBE)?;
+    if !evm.transfer(pair_address, PROBE, amount)? {
+        if evm.paused()? {
+            behavior.paused = true;
+        } else {
+            behavior.blacklisting = true;
+        }
+        return Ok(behavior);
+    }
+    let received = evm.balance_of(PROBE)?.saturating_sub(probe_balance);
+    behavior.buy_fee_bps = fee_bps(amount, received);
+    behavior.rebasing = evm.balance_of_at(PROBE, header.timestamp + REBASE_DELAY)? != probe_balance + received;
+    if received.is_zero() {
+        return Ok(behavior);
+    }
+
+    // sell: the probe sends the received tokens back
+    let pair_balance = evm.balance_of(pair_address)?;
+    if !evm.transfer(PROBE, pair_address, received)? {
+        behavior.blacklisting = true;
+        return Ok(behavior);
+    }
+    behavior.sell_fee_bps = fee_bps(received, evm.balance_of(pair_address)?.saturating_sub(pair_balance));
+
+    Ok(behavior)
+}
+
+/// Share of `sent` missing from `received` in basis points, computed in `U512` as a huge balance times 10000 overflows `U256`.
+fn fee_bps(sent: U256, received: U256) -> u32 {
+    if received >= sent {
+        return 0;
+    }
+    (U512::from(sent - received) * U512::from(10_000) / U512::from(sent)).to::<u32>()
+}
+
+struct TokenSimulator<T> {
+    db: CacheDB<StateProviderDatabase<T>>,
+    token: Address,
+    block: BlockEnv,
+}
+
+impl<T: StateProvider> TokenSimulator<T> {
+    fn balance_of(&mut self, owner: Address) -> eyre::Result<U256> {
+        self.balance_of_at(owner, self.block.timestamp.to())
+    }
+
+    fn balance_of_at(&mut self, owner: Address, timestamp: u64) -> eyre::Result<U256> {
+        match self.call(owner, IERC20::balanceOfCall { owner }.abi_encode(), timestamp, false)? {
+            Some(output) => Ok(IERC20::balanceOfCall::abi_decode_returns(&output, false)?._0),
+            None => Err(eyre!("BALANCE_OF_REVERTED")),
+        }
+    }
+
+    fn paused(&mut self) -> eyre::Result<bool> {
+        // tokens without `paused()` revert
+        Ok(match self.call(Address::ZERO, IERC20::pausedCall {}.abi_encode(), self.block.timestamp.to(), false)? {
+            Some(output) => IERC20::pausedCall::abi_decode_returns(&output, false).map(|paused| paused._0).unwrap_or_default(),
+            None => false,
+        })
+    }
+
+    /// Transfer and commit the state. Tokens like USDT return nothing on success.
+    fn transfer(&mut self, from: Address, to: Address, amount: U256) -> eyre::Result<bool> {
+        Ok(match self.call(from, IERC20::transferCall { to, amount }.abi_encode(), self.block.timestamp.to(), true)? {
+            Some(output) => {
+                output.is_empty() || IERC20::transferCall::abi_decode_returns(&output, false).map(|success| success._0).unwrap_or_default()
+            }
+            None => false,
+        })
+    }
+
+    /// Call the token as the caller at the timestamp, a contract like the pair is allowed. The call pays no gas, the base fee
+    /// is not checked. Returns `None` if the call reverted.
+    fn call(&mut self, caller: Address, data: Vec<u8>, timestamp: u64, commit: bool) -> eyre::Result<Option<Bytes>> {
+        let mut evm = Evm::builder()
+            .with_db(&mut self.db)
+            .modify_cfg_env(|cfg| {
+                cfg.disable_eip3607 = true;
+                cfg.disable_base_fee = true;
+            })
+            .modify_block_env(|block| {
+                *block = self.block.clone();
+                block.timestamp = U256::from(timestamp);
+            })
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TxKind::Call(self.token);
+                tx.data = data.into();
+                tx.gas_limit = GAS_LIMIT;
+            })
+            .build();
+        let result = if commit { evm.transact_commit()? } else { evm.transact()?.result };
+        Ok(if result.is_success() { Some(result.into_output().unwrap_or_default()) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{hex, keccak256};
+    use reth_db::tables;
+    use reth_db::transaction::DbTxMut;
+    use reth_primitives::{Account, Bytecode, StorageEntry};
+    use reth_stages::test_utils::TestStageDB;
+
+    // Minimal ERC20 with `balanceOf` and `transfer`, balances are stored at the slot of the address. The fee token burns
+    // 10% of each transfer. The paused token reverts all transfers and returns true for `paused()`, the blacklisting token
+    // reverts all transfers without `paused()`. The balances of the rebasing token grow with `timestamp / 1_700_000_000`.
+    // The launch token reverts transfers before block 100.
+    const STANDARD_TOKEN: &str = "60003560e01c806370a0823114601f578063a9059cbb14602c575b600080fd5b6004355460005260206000f35b6024353354818110601a57819003335560005b5b900360043580548201905550600160005260206000f3";
+    const FEE_TOKEN: &str = "60003560e01c806370a0823114601f578063a9059cbb14602c575b600080fd5b6004355460005260206000f35b6024353354818110601a578190033355600a8104900360043580548201905550600160005260206000f3";
+    const PAUSED_TOKEN: &str = "60003560e01c806370a08231146034578063a9059cbb1460415780635c975abb146029575b600080fd5b600160005260206000f35b6004355460005260206000f35b6024566024353354818110602457819003335560005b5b900360043580548201905550600160005260206000f3";
+    const BLACKLISTING_TOKEN: &str = "60003560e01c806370a0823114601f578063a9059cbb14602c575b600080fd5b6004355460005260206000f35b601a566024353354818110601a57819003335560005b5b900360043580548201905550600160005260206000f3";
+    const REBASING_TOKEN: &str = "60003560e01c806370a0823114601f578063a9059cbb146035575b600080fd5b600435544202636553f100900460005260206000f35b6024353354818110601a57819003335560005b5b900360043580548201905550600160005260206000f3";
+    const LAUNCH_TOKEN: &str = "60003560e01c806370a0823114601f578063a9059cbb14602c575b600080fd5b6004355460005260206000f35b60644310601a576024353354818110601a57819003335560005b5b900360043580548201905550600160005260206000f3";
+
+    fn header(number: u64) -> Header {
+        Header { number, timestamp: 1_700_000_000, base_fee_per_gas: Some(7_000_000_000), ..Default::default() }
+    }
+
+    // the pair holds 1e18, a day of the rebasing token adds less than one unit to smaller balances
+    fn insert_token(test_db: &TestStageDB, token: Address, code: &str, pair_address: Address) -> eyre::Result<()> {
+        let code = Bytes::from(hex::decode(code)?);
+        let code_hash = keccak256(&code);
+        test_db.insert_accounts_and_storages(vec![(
+            token,
+            (
+                Account { bytecode_hash: Some(code_hash), ..Default::default() },
+                vec![StorageEntry::new(pair_address.into_word(), U256::from(10).pow(U256::from(18)))],
+            ),
+        )])?;
+        test_db.commit(|tx| Ok(tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code))?))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_token() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        // the low addresses are precompiles
+        let (standard_token, fee_token) = (Address::with_last_byte(0x11), Address::with_last_byte(0x12));
+        insert_token(&test_db, standard_token, STANDARD_TOKEN, pair_address)?;
+        insert_token(&test_db, fee_token, FEE_TOKEN, pair_address)?;
+
+        let behavior = classify_token(test_db.factory.latest()?, standard_token, pair_address, &header(1))?;
+        assert!(behavior.is_standard());
+
+        let behavior = classify_token(test_db.factory.latest()?, fee_token, pair_address, &header(1))?;
+        assert_eq!(behavior, TokenBehavior { buy_fee_bps: 1000, sell_fee_bps: 1000, ..Default::default() });
+
+        // a token without balance in the pair can not be probed
+        let err = classify_token(test_db.factory.latest()?, fee_token, Address::with_last_byte(3), &header(1)).unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidPool { address, .. }) if *address == Address::with_last_byte(3))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_token_behaviors() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let tokens = [0x11, 0x12, 0x13, 0x14].map(Address::with_last_byte);
+        for (token, code) in tokens.iter().zip([PAUSED_TOKEN, BLACKLISTING_TOKEN, REBASING_TOKEN, LAUNCH_TOKEN]) {
+            insert_token(&test_db, *token, code, pair_address)?;
+        }
+
+        let behavior = classify_token(test_db.factory.latest()?, tokens[0], pair_address, &header(1))?;
+        assert_eq!(behavior, TokenBehavior { paused: true, ..Default::default() });
+
+        let behavior = classify_token(test_db.factory.latest()?, tokens[1], pair_address, &header(1))?;
+        assert_eq!(behavior, TokenBehavior { blacklisting: true, ..Default::default() });
+
+        let behavior = classify_token(test_db.factory.latest()?, tokens[2], pair_address, &header(1))?;
+        assert_eq!(behavior, TokenBehavior { rebasing: true, ..Default::default() });
+
+        // the block number of the header is used
+        let behavior = classify_token(test_db.factory.latest()?, tokens[3], pair_address, &header(99))?;
+        assert_eq!(behavior, TokenBehavior { blacklisting: true, ..Default::default() });
+        assert!(classify_token(test_db.factory.latest()?, tokens[3], pair_address, &header(100))?.is_standard());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_bps_of_huge_balance() {
+        assert_eq!(fee_bps(U256::from(1000), U256::from(900)), 1000);
+        assert_eq!(fee_bps(U256::from(1000), U256::from(1100)), 0);
+        // the difference times 10000 overflows U256
+        assert_eq!(fee_bps(U256::MAX, U256::MAX / U256::from(10) * U256::from(9)), 1000);
+        assert_eq!(fee_bps(U256::MAX, U256::ZERO), 10_000);
+    }
+
+    #[test]
+    fn test_token_behavior_cache() -> eyre::Result<()> {
+        let mut cache = TokenBehaviorCache::default();
+        cache.tokens.insert(Address::with_last_byte(2), TokenBehavior { buy_fee_bps: 1000, ..Default::default() });
+
+        let path = std::env::temp_dir().join(format!("dexsync_tokens_{}", std::process::id()));
+        cache.save(&path, Address::with_last_byte(9))?;
+        let loaded = TokenBehaviorCache::load(&path, Address::with_last_byte(9))?;
+        assert_eq!(loaded.tokens, cache.tokens);
+        assert!(TokenBehaviorCache::load(&path, Address::with_last_byte(8))?.tokens.is_empty());
+        std::fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
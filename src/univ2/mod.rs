@@ -1,8 +1,14 @@
+mod univ2_discovery;
 mod univ2_factory;
 mod univ2_pair;
 
 use alloy_primitives::{address, Address};
-pub use univ2_factory::{PoolFilter, UniV2Factory};
-pub use univ2_pair::{UniV2Pair, UniV2PairReserve};
+pub use univ2_discovery::{discover_univ2_pairs, pair_created_spec, pairs_from_events, UNI_V2_FACTORY_START_BLOCK};
+pub use univ2_factory::{read_pairs_interval, read_univ2_pairs_length, PoolFilter, UniV2Factory};
+pub use univ2_pair::{
+    compute_pair_address, compute_pair_twap, decode_pair_reserves, read_pair, read_pair_activity, read_pair_if_exists, read_pair_reserves,
+    read_pair_reserves_proof, read_pair_state, read_pair_twap, read_pairs_reserves_history, PairCreation, UniV2Pair, UniV2PairReserve,
+    UniV2PairReserveHistory, UniV2PairState, UniV2Twap, PAIR_INIT_CODE_HASH, PAIR_STATE_SLOTS,
+};
 
 pub const UNI_V2_FACTORY: Address = address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f");
@@ -0,0 +1,103 @@
+use crate::events::{EventIndexer, EventSpec, IndexedEvent};
+use crate::univ2::univ2_pair::{PairCreation, UniV2Pair};
+use crate::utils::DexSyncError;
+use alloy_primitives::{Address, LogData};
+use alloy_sol_types::{sol, SolEvent};
+use reth_storage_api::BlockReader;
+
+sol! {
+    event PairCreated(address indexed token0, address indexed token1, address pair, uint256 index);
+}
+
+/// Deployment block of the Uniswap V2 factory
+pub const UNI_V2_FACTORY_START_BLOCK: u64 = 10000835;
+
+/// Spec of the `PairCreated` event of the factory for the event indexer.
+pub fn pair_created_spec(factory_address: Address, start_block: u64) -> EventSpec {
+    EventSpec::of::<PairCreated>(factory_address, start_block)
+}
+
+/// Discover the pairs of the factory from its `PairCreated` logs up to `to_block`. Unlike reading `allPairs` the pairs
+/// carry their creation block, transaction and timestamp. The result is ordered by creation.
+pub fn discover_univ2_pairs<P: BlockReader + Sync>(
+    provider: &P,
+    factory_address: Address,
+    start_block: u64,
+    to_block: u64,
+) -> eyre::Result<Vec<UniV2Pair>> {
+    let events = EventIndexer::new(vec![pair_created_spec(factory_address, start_block)]).index_all(provider, to_block)?;
+    Ok(pairs_from_events(factory_address, &events)?)
+}
+
+/// Decode the pairs from indexed `PairCreated` logs, other logs are skipped.
+pub fn pairs_from_events(factory_address: Address, events: &[IndexedEvent<LogData>]) -> Result<Vec<UniV2Pair>, DexSyncError> {
+    let mut pairs = vec![];
+    for event in events {
+        if event.address != factory_address || event.signature() != Some(PairCreated::SIGNATURE_HASH) {
+            continue;
+        }
+        let created = event.decode::<PairCreated>()?;
+        pairs.push(UniV2Pair {
+            address: created.event.pair,
+            token0: created.event.token0,
+            token1: created.event.token1,
+            creation: Some(PairCreation {
+                block_number: created.block.number,
+                block_hash: created.block.hash,
+                tx_hash: created.tx_hash,
+                timestamp: created.block_timestamp,
+            }),
+        });
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ2::{PoolFilter, UniV2PairReserve, UNI_V2_FACTORY};
+    use alloy::rpc::types::BlockNumHash;
+    use alloy_primitives::aliases::U112;
+    use alloy_primitives::{address, B256, U256};
+    use alloy_sol_types::SolValue;
+
+    #[test]
+    fn test_pairs_from_events() -> eyre::Result<()> {
+        let (token0, token1) = (address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"), address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"));
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let event = IndexedEvent {
+            block: BlockNumHash::new(10008355, B256::with_last_byte(1)),
+            block_timestamp: 1588710145,
+            tx_hash: B256::with_last_byte(2),
+            tx_index: 0,
+            log_index: 0,
+            address: UNI_V2_FACTORY,
+            event: LogData::new_unchecked(
+                vec![PairCreated::SIGNATURE_HASH, token0.into_word(), token1.into_word()],
+                (pair_address, U256::from(1)).abi_encode_params().into(),
+            ),
+        };
+        // same event from another factory
+        let other = IndexedEvent { address: Address::with_last_byte(1), ..event.clone() };
+
+        let pairs = pairs_from_events(UNI_V2_FACTORY, &[event, other])?;
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!((pair.address, pair.token0, pair.token1), (pair_address, token0, token1));
+        assert_eq!(pair.creation.as_ref().unwrap().block_number, 10008355);
+        assert_eq!(pair.age_at(1588710145 + 60), Some(60));
+
+        // filter pairs younger than one day
+        let reserve = UniV2PairReserve { block_timestamp_last: 1588710145, reserve0: U112::from(1), reserve1: U112::from(1) };
+        let mut filter = PoolFilter::new();
+        filter.min_age(86400, 1588710145 + 3600);
+        assert!(!filter.matches(pair, &reserve));
+        filter.min_age(86400, 1588710145 + 2 * 86400);
+        assert!(filter.matches(pair, &reserve));
+        // the age of a pair read from `allPairs` is unknown
+        assert!(!filter.matches(&UniV2Pair { creation: None, ..pair.clone() }, &reserve));
+        assert!(PoolFilter::new().matches(&UniV2Pair { creation: None, ..pair.clone() }, &reserve));
+
+        Ok(())
+    }
+}
@@ -1,21 +1,29 @@
 use crate::univ2::univ2_pair::UniV2Pair;
-use crate::univ2::{univ2_pair, UniV2PairReserve};
-use crate::utils::{read_array_item, state_provider, CacheError, DexSyncCache};
-use alloy::eips::BlockNumberOrTag;
-use alloy_primitives::{b256, keccak256, Address, B256, U160};
-use alloy_sol_types::SolValue;
-use eyre::eyre;
-use lazy_static::lazy_static;
+use crate::univ2::{univ2_pair, UniV2PairReserve, UniV2PairState};
+use crate::utils::chunk_reader::ChunkReader;
+use crate::utils::progress::{LoadPhase, NoopProgress, ProgressObserver, ProgressTracker};
+use crate::utils::telemetry::{record_cache_lookup, record_pools_loaded};
+use crate::utils::{
+    read_required_storage, resolve_block, state_provider, CacheError, DexSyncCache, DexSyncError, DynArray, Field, LoadChunk, LoadFailure,
+    LoadMode, Mapping, PoolDeployment, PoolStream,
+};
+use alloy::eips::{BlockNumHash, BlockNumberOrTag};
+use alloy_primitives::{b256, Address, B256};
 use reth_provider::{StateProvider, StateProviderFactory};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::debug;
 
+const GET_PAIR_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000002");
 const ALL_PAIRS_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000003");
+/// `mapping(address => mapping(address => address)) getPair`, set for both token orders
+const GET_PAIR: Mapping<Address, Mapping<Address, Field<Address>>> = Mapping::new(GET_PAIR_SLOT);
 
-lazy_static! {
-    static ref ALL_PAIRS_START_SLOT: B256 = keccak256(ALL_PAIRS_SLOT.abi_encode());
-}
+/// `address[] allPairs`
+const ALL_PAIRS: DynArray<Field<Address>> = DynArray::new(ALL_PAIRS_SLOT);
+/// Pairs read with one state provider, a long-running transaction fails
+const CHUNK_SIZE: usize = 1000;
 
 // Smart caching all pairs with address, token0 and token1. Only new pairs will be loaded.
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -33,21 +41,53 @@ impl UniV2FactoryCache {
 pub struct PoolFilter {
     // Exclusive filter all pairs with block timestamp after this value.
     block_timestamp_after: u32,
+    // Exclusive filter all pairs created at or after this timestamp. Pairs with unknown creation are skipped.
+    created_before: Option<u64>,
 }
 
 impl PoolFilter {
     pub fn new() -> Self {
-        Self { block_timestamp_after: 0 }
+        Self { block_timestamp_after: 0, created_before: None }
     }
     pub fn block_timestamp_after(&mut self, block_timestamp_after: u32) -> &mut Self {
         self.block_timestamp_after = block_timestamp_after;
         self
     }
+    /// Skip pairs younger than `min_age` seconds at the timestamp, e.g. freshly created honeypots. The creation is only known for
+    /// pairs from `discover_univ2_pairs`, the loaders reading `allPairs` reject the filter.
+    pub fn min_age(&mut self, min_age: u64, timestamp: u64) -> &mut Self {
+        self.created_before = Some(timestamp.saturating_sub(min_age));
+        self
+    }
+
+    /// Whether the pair with its reserves passes the filter.
+    pub fn matches(&self, pair: &UniV2Pair, reserve: &UniV2PairReserve) -> bool {
+        if reserve.block_timestamp_last <= self.block_timestamp_after {
+            return false;
+        }
+        match (&pair.creation, self.created_before) {
+            (Some(creation), Some(created_before)) => creation.timestamp < created_before,
+            (None, Some(_)) => false,
+            (_, None) => true,
+        }
+    }
+
+    /// Pairs read from `allPairs` have no creation, reject a filter that would skip all of them.
+    fn check_all_pairs(&self) -> Result<(), DexSyncError> {
+        match self.created_before {
+            Some(_) => Err(DexSyncError::UnsupportedFilter("min_age requires the creation of pairs from discover_univ2_pairs")),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct UniV2Factory {
     pub pairs: Vec<(UniV2Pair, UniV2PairReserve)>,
+    /// Pairs skipped by a lenient load
+    pub failures: Vec<LoadFailure>,
+    /// Block of the snapshot all pairs and reserves are read at
+    pub block: BlockNumHash,
 }
 
 impl UniV2Factory {
@@ -58,6 +98,31 @@ impl UniV2Factory {
         filter: &PoolFilter,
         cache_path: Option<PathBuf>,
     ) -> eyre::Result<Self> {
+        Self::load_pairs_with_progress(
+            provider_factory,
+            block_number_or_tag,
+            factory_address,
+            filter,
+            cache_path,
+            LoadMode::Strict,
+            &NoopProgress,
+        )
+    }
+
+    /// Same as `load_pairs` with the load mode, notifying the observer after each chunk of pairs read from the factory and after
+    /// each chunk of reserves read in a second `LoadPhase::Reserves` pass. The cache is only written if no pair failed.
+    pub fn load_pairs_with_progress<P: StateProviderFactory>(
+        provider_factory: &P,
+        block_number_or_tag: &BlockNumberOrTag,
+        factory_address: Address,
+        filter: &PoolFilter,
+        cache_path: Option<PathBuf>,
+        mode: LoadMode,
+        observer: &dyn ProgressObserver,
+    ) -> eyre::Result<Self> {
+        filter.check_all_pairs()?;
+        let block = resolve_block(provider_factory, block_number_or_tag)?;
+        let block_number_or_tag = &BlockNumberOrTag::Number(block.number);
         let cached = Self::read_cached_pairs_if_exists(&cache_path, factory_address)?;
         // Convert cached pools to pairs
         let mut pairs = cached.pairs;
@@ -65,31 +130,118 @@ impl UniV2Factory {
         // Add new pairs since last cache write from pair index
         let start_idx = if !pairs.is_empty() { pairs.len() - 1 } else { 0 };
         debug!("Loaded new pools: {}", start_idx);
-        let (new_pairs, _) = read_univ2_pairs(provider_factory, block_number_or_tag, factory_address, start_idx)?;
+        let mut failures = vec![];
+        let (new_pairs, _) =
+            read_univ2_pairs_observed(provider_factory, block_number_or_tag, factory_address, start_idx, mode, &mut failures, observer)?;
         pairs.extend(new_pairs);
 
         // populate reserves for pairs
-        let pairs_and_reserves = read_univ2_pairs_reserves(provider_factory, block_number_or_tag, pairs, filter)?;
+        let pairs_and_reserves =
+            read_univ2_pairs_reserves_observed(provider_factory, block_number_or_tag, pairs, filter, mode, &mut failures, observer)?;
+        record_pools_loaded("uniswap_v2", pairs_and_reserves.len());
 
-        if cache_path.is_some() {
+        // Skipped pairs would shift the pair index of the cache
+        if cache_path.is_some() && failures.is_empty() {
             let pairs = pairs_and_reserves.iter().map(|(pair, _)| pair.clone()).collect();
             let cache = UniV2FactoryCache { pairs };
             DexSyncCache::save(&cache_path.unwrap(), factory_address, cache)?;
         }
 
-        Ok(Self { pairs: pairs_and_reserves })
+        Ok(Self { pairs: pairs_and_reserves, failures, block })
+    }
+
+    /// Stream pairs with reserves in chunks as they are read, starting at the pair index. Pairs skipped in lenient mode are the
+    /// failures of their chunk. Must be called within a tokio runtime.
+    pub fn stream_pairs<P: StateProviderFactory + Send + Sync + 'static>(
+        provider_factory: Arc<P>,
+        block_number_or_tag: BlockNumberOrTag,
+        factory_address: Address,
+        filter: PoolFilter,
+        start_idx: usize,
+        chunk_size: usize,
+        mode: LoadMode,
+    ) -> PoolStream<(UniV2Pair, UniV2PairReserve)> {
+        PoolStream::spawn(move |sender| {
+            filter.check_all_pairs()?;
+            let block = resolve_block(provider_factory.as_ref(), &block_number_or_tag)?;
+            let block_number_or_tag = BlockNumberOrTag::Number(block.number);
+            let provider = state_provider(provider_factory.as_ref(), &block_number_or_tag)?;
+            let pairs_length = read_univ2_pairs_length(&provider, factory_address)?;
+            let total = pairs_length.saturating_sub(start_idx);
+
+            let reader = ChunkReader { block_number_or_tag, chunk_size, mode, metric: "univ2_pairs_chunk" };
+            reader.read(
+                provider_factory.as_ref(),
+                start_idx..pairs_length,
+                |_| factory_address,
+                |provider, idx| {
+                    let pair = read_pair_at(provider, factory_address, idx)?;
+                    let pair_reserves = univ2_pair::read_pair_reserves(provider, pair.address)?;
+                    Ok(filter.matches(&pair, &pair_reserves).then_some((pair, pair_reserves)))
+                },
+                |items, failures, end| {
+                    record_pools_loaded("uniswap_v2", items.len());
+                    sender.send(LoadChunk { items, failures, done: end - start_idx, total, block })
+                },
+            )
+        })
+    }
+
+    /// Slots of the factory the pairs are read from, the `allPairs` length and items and the `getPair` entries of both token
+    /// orders. The pair index is the position in `pairs`, so the load must not be filtered.
+    pub fn storage_slots(&self) -> Vec<B256> {
+        let mut slots = vec![ALL_PAIRS_SLOT];
+        for (idx, (pair, _)) in self.pairs.iter().enumerate() {
+            slots.push(ALL_PAIRS.item_slot(idx));
+            slots.push(GET_PAIR.entry(&pair.token0).entry(&pair.token1).slot);
+            slots.push(GET_PAIR.entry(&pair.token1).entry(&pair.token0).slot);
+        }
+        slots
+    }
+
+    /// Look up the pair of two tokens in the factory's `getPair` mapping and read its state, without loading the other pairs.
+    /// Returns `None` if the factory has no pair of the tokens.
+    pub fn get_pair<T: StateProvider>(
+        provider: T,
+        factory_address: Address,
+        token_a: Address,
+        token_b: Address,
+    ) -> eyre::Result<Option<(UniV2Pair, UniV2PairState)>> {
+        let pair_address = GET_PAIR.entry(&token_a).entry(&token_b).read(&provider, factory_address)?;
+        if pair_address.is_zero() {
+            return Ok(None);
+        }
+        let pair = univ2_pair::read_pair(&provider, pair_address)?;
+        let state = univ2_pair::read_pair_state(&provider, pair_address)?;
+        Ok(Some((pair, state)))
+    }
+
+    /// Drop the pairs not deployed by the factory of the deployment or running other code, and record them as failures.
+    /// The index of a failure is the position in `pairs` before the check.
+    pub fn retain_verified_pairs<T: StateProvider>(&mut self, provider: T, deployment: &PoolDeployment) -> eyre::Result<()> {
+        let pairs = std::mem::take(&mut self.pairs);
+        for (idx, (pair, reserve)) in pairs.into_iter().enumerate() {
+            let verified = deployment.verify(&provider, pair.address, pair.salt()).map_err(eyre::Report::from);
+            if LoadMode::Lenient.check(verified, pair.address, idx, &mut self.failures)?.is_some() {
+                self.pairs.push((pair, reserve));
+            }
+        }
+        Ok(())
     }
 
     fn read_cached_pairs_if_exists(cache_path: &Option<PathBuf>, factory_address: Address) -> eyre::Result<UniV2FactoryCache> {
         let factory = match &cache_path {
             Some(cache_path) => {
                 let factory = match DexSyncCache::load::<UniV2FactoryCache>(cache_path, factory_address) {
-                    Ok(univ2_factory) => univ2_factory,
-                    Err(cache_error) => match cache_error {
-                        CacheError::Io(e) => return Err(eyre!(e)),
-                        CacheError::Bincode(e) => return Err(eyre!(e)),
-                        CacheError::FileNotFound => UniV2FactoryCache::new(),
-                    },
+                    Ok(univ2_factory) => {
+                        record_cache_lookup(true);
+                        univ2_factory
+                    }
+                    Err(CacheError::FileNotFound) => {
+                        record_cache_lookup(false);
+                        UniV2FactoryCache::new()
+                    }
+                    Err(cache_error) => return Err(DexSyncError::Cache(cache_error).into()),
                 };
                 debug!("Loaded pools cache: {}", factory.pairs.len());
                 factory
@@ -106,28 +258,56 @@ pub fn read_univ2_pairs<P: StateProviderFactory>(
     block_number_or_tag: &BlockNumberOrTag,
     factory_address: Address,
     start_idx: usize,
+) -> eyre::Result<(Vec<UniV2Pair>, usize)> {
+    // all chunks are read at the same block
+    let block_number_or_tag = &BlockNumberOrTag::Number(resolve_block(provider_factory, block_number_or_tag)?.number);
+    read_univ2_pairs_observed(
+        provider_factory,
+        block_number_or_tag,
+        factory_address,
+        start_idx,
+        LoadMode::Strict,
+        &mut vec![],
+        &NoopProgress,
+    )
+}
+
+fn read_univ2_pairs_observed<P: StateProviderFactory>(
+    provider_factory: &P,
+    block_number_or_tag: &BlockNumberOrTag,
+    factory_address: Address,
+    start_idx: usize,
+    mode: LoadMode,
+    failures: &mut Vec<LoadFailure>,
+    observer: &dyn ProgressObserver,
 ) -> eyre::Result<(Vec<UniV2Pair>, usize)> {
     let provider = state_provider(provider_factory, block_number_or_tag)?;
-    let pairs_length = match provider.storage(factory_address, ALL_PAIRS_SLOT)? {
-        None => return Err(eyre!("Invalid pair length")),
-        Some(l) => l.to::<usize>(),
-    };
+    let pairs_length = read_univ2_pairs_length(&provider, factory_address)?;
 
-    let chunk_size: usize = 1000;
     let mut pairs = Vec::new();
-
-    // Reading in chunks to avoid long transaction error.
-    for start in (start_idx..pairs_length).step_by(chunk_size) {
-        let end = std::cmp::min(start + chunk_size, pairs_length);
-        // To avoid long-running transactions we create a now provider for each chunk.
-        let provider = state_provider(provider_factory, block_number_or_tag)?;
-        let pairs_chunk = read_pairs_interval(&provider, factory_address, start, end)?;
-        pairs.extend(pairs_chunk);
-    }
+    let progress = ProgressTracker::new(observer, LoadPhase::Pools, pairs_length.saturating_sub(start_idx));
+    let reader = ChunkReader { block_number_or_tag: *block_number_or_tag, chunk_size: CHUNK_SIZE, mode, metric: "univ2_pairs_chunk" };
+    reader.read(
+        provider_factory,
+        start_idx..pairs_length,
+        |_| factory_address,
+        |provider, idx| read_pair_at(provider, factory_address, idx).map(Some),
+        |chunk, chunk_failures, end| {
+            pairs.extend(chunk);
+            failures.extend(chunk_failures);
+            progress.update(end - start_idx, end - 1);
+            true
+        },
+    )?;
 
     Ok((pairs, pairs_length))
 }
 
+/// Read the number of pairs of the factory contract (`allPairs.length`).
+pub fn read_univ2_pairs_length<T: StateProvider>(provider: T, factory_address: Address) -> eyre::Result<usize> {
+    Ok(read_required_storage(&provider, factory_address, ALL_PAIRS_SLOT)?.to::<usize>())
+}
+
 #[allow(dead_code)]
 /// Read all univ2 pairs from the factory contract. The result is not sorted.
 pub fn read_univ2_pairs_full<T: StateProvider>(
@@ -135,10 +315,7 @@ pub fn read_univ2_pairs_full<T: StateProvider>(
     factory_address: Address,
     start_idx: usize,
 ) -> eyre::Result<Vec<(UniV2Pair, UniV2PairReserve)>> {
-    let pairs_length = match provider.storage(factory_address, ALL_PAIRS_SLOT)? {
-        None => return Err(eyre!("Invalid pair length")),
-        Some(l) => l.to::<usize>(),
-    };
+    let pairs_length = read_univ2_pairs_length(&provider, factory_address)?;
 
     let chunk_size: usize = 5000;
     let mut pairs = Vec::new();
@@ -160,37 +337,58 @@ pub fn read_univ2_pairs_reserves<P: StateProviderFactory>(
     pairs: Vec<UniV2Pair>,
     filter: &PoolFilter,
 ) -> eyre::Result<Vec<(UniV2Pair, UniV2PairReserve)>> {
-    let mut pairs_with_reserves = Vec::new();
+    read_univ2_pairs_reserves_observed(provider_factory, block_number_or_tag, pairs, filter, LoadMode::Strict, &mut vec![], &NoopProgress)
+}
 
-    let provider = state_provider(provider_factory, block_number_or_tag)?;
-    for pair in pairs {
-        let pair_reserves = univ2_pair::read_pair_reserves(&provider, pair.address)?;
-        if pair_reserves.block_timestamp_last <= filter.block_timestamp_after {
-            continue;
-        }
-        pairs_with_reserves.push((pair, pair_reserves));
-    }
+/// Read the reserves of the pairs, a failure has the position in `pairs` as index.
+fn read_univ2_pairs_reserves_observed<P: StateProviderFactory>(
+    provider_factory: &P,
+    block_number_or_tag: &BlockNumberOrTag,
+    pairs: Vec<UniV2Pair>,
+    filter: &PoolFilter,
+    mode: LoadMode,
+    failures: &mut Vec<LoadFailure>,
+    observer: &dyn ProgressObserver,
+) -> eyre::Result<Vec<(UniV2Pair, UniV2PairReserve)>> {
+    let mut pairs_with_reserves = Vec::new();
+    let progress = ProgressTracker::new(observer, LoadPhase::Reserves, pairs.len());
+    let reader = ChunkReader { block_number_or_tag: *block_number_or_tag, chunk_size: CHUNK_SIZE, mode, metric: "univ2_reserves_chunk" };
+    reader.read(
+        provider_factory,
+        0..pairs.len(),
+        |idx| pairs[idx].address,
+        |provider, idx| {
+            let pair_reserves = univ2_pair::read_pair_reserves(provider, pairs[idx].address)?;
+            Ok(filter.matches(&pairs[idx], &pair_reserves).then(|| (pairs[idx].clone(), pair_reserves)))
+        },
+        |chunk, chunk_failures, end| {
+            pairs_with_reserves.extend(chunk);
+            failures.extend(chunk_failures);
+            progress.update(end, end - 1);
+            true
+        },
+    )?;
 
     Ok(pairs_with_reserves)
 }
 
 fn read_pair_address<T: StateProvider>(provider: T, factory_address: Address, idx: usize) -> eyre::Result<Address> {
-    match read_array_item(&provider, factory_address, *ALL_PAIRS_START_SLOT, idx)? {
-        None => Err(eyre!("PAIR_NOT_FOUND")),
-        Some(storage_value) => Ok(Address::from(U160::from(storage_value))),
-    }
+    Ok(ALL_PAIRS.item(idx).read_required(&provider, factory_address)?)
 }
 
-fn read_pairs_interval<T: StateProvider>(provider: T, factory_address: Address, start: usize, end: usize) -> eyre::Result<Vec<UniV2Pair>> {
-    let mut pairs = Vec::new();
-
-    for idx in start..end {
-        let pair_address = read_pair_address(&provider, factory_address, idx)?;
-        let pair = univ2_pair::read_pair(&provider, pair_address)?;
-        pairs.push(pair);
-    }
+/// Read the pair with the index from the factory contract.
+fn read_pair_at<T: StateProvider>(provider: T, factory_address: Address, idx: usize) -> eyre::Result<UniV2Pair> {
+    univ2_pair::read_pair(&provider, read_pair_address(&provider, factory_address, idx)?)
+}
 
-    Ok(pairs)
+/// Read the pairs with an index in `start..end` from the factory contract.
+pub fn read_pairs_interval<T: StateProvider>(
+    provider: T,
+    factory_address: Address,
+    start: usize,
+    end: usize,
+) -> eyre::Result<Vec<UniV2Pair>> {
+    (start..end).map(|idx| read_pair_at(&provider, factory_address, idx)).collect()
 }
 
 #[allow(dead_code)]
@@ -216,7 +414,11 @@ fn read_pairs_full_interval<T: StateProvider>(
 mod tests {
     use super::*;
     use crate::univ2::UNI_V2_FACTORY;
+    use crate::utils::LoadProgress;
     use alloy_primitives::{address, U256};
+    use futures::StreamExt;
+    use reth_db::tables;
+    use reth_db::transaction::DbTxMut;
     use reth_primitives::{Account, StorageEntry};
     use reth_stages::test_utils::TestStageDB;
 
@@ -251,4 +453,269 @@ mod tests {
         assert_eq!(pair_address, pair_address_18);
         Ok(())
     }
+
+    fn insert_pairs(test_db: &TestStageDB, pair_addresses: &[Address]) -> eyre::Result<()> {
+        let token = U256::from_be_slice(address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").as_slice());
+        let mut factory_storage = vec![StorageEntry::new(ALL_PAIRS_SLOT, U256::from(pair_addresses.len()))];
+        let mut accounts = vec![];
+        for (idx, pair_address) in pair_addresses.iter().enumerate() {
+            let storage_key = ALL_PAIRS.item_slot(idx);
+            factory_storage.push(StorageEntry::new(storage_key, U256::from_be_slice(pair_address.as_slice())));
+            accounts.push((
+                *pair_address,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(B256::with_last_byte(6), token),
+                        StorageEntry::new(B256::with_last_byte(7), token + U256::from(1)),
+                        // reserves with block timestamp 1 + idx
+                        StorageEntry::new(B256::with_last_byte(8), U256::from(1 + idx) << 224),
+                    ],
+                ),
+            ));
+        }
+        accounts.push((UNI_V2_FACTORY, (Account::default(), factory_storage)));
+        test_db.insert_accounts_and_storages(accounts)?;
+        // hash of the snapshot block
+        test_db.commit(|tx| Ok(tx.put::<tables::CanonicalHeaders>(0, B256::with_last_byte(1))?))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_pairs_with_progress() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_addresses = [address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc"), address!("5d27df1a6e03254e4f1218607d8e073667ffae2f")];
+        insert_pairs(&test_db, &pair_addresses)?;
+
+        let progress = std::sync::Mutex::new(vec![]);
+        let observer = |p: &LoadProgress| progress.lock().unwrap().push(p.clone());
+        let factory = UniV2Factory::load_pairs_with_progress(
+            &test_db.factory,
+            &BlockNumberOrTag::Latest,
+            UNI_V2_FACTORY,
+            &PoolFilter::new(),
+            None,
+            LoadMode::Strict,
+            &observer,
+        )?;
+
+        assert_eq!(factory.pairs.len(), 2);
+        assert_eq!(factory.block, BlockNumHash::new(0, B256::with_last_byte(1)));
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 2);
+        assert_eq!((progress[0].phase, progress[0].done, progress[0].total, progress[0].factory_index), (LoadPhase::Pools, 2, 2, 1));
+        assert_eq!((progress[1].phase, progress[1].done, progress[1].total, progress[1].factory_index), (LoadPhase::Reserves, 2, 2, 1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_pairs_lenient() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let broken_address = address!("5d27df1a6e03254e4f1218607d8e073667ffae2f");
+        let token = U256::from_be_slice(address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").as_slice());
+        let factory_storage = vec![
+            StorageEntry::new(ALL_PAIRS_SLOT, U256::from(2)),
+            StorageEntry::new(ALL_PAIRS.item_slot(0), U256::from_be_slice(pair_address.as_slice())),
+            StorageEntry::new(ALL_PAIRS.item_slot(1), U256::from_be_slice(broken_address.as_slice())),
+        ];
+        let pair_storage = vec![
+            StorageEntry::new(B256::with_last_byte(6), token),
+            StorageEntry::new(B256::with_last_byte(7), token + U256::from(1)),
+            StorageEntry::new(B256::with_last_byte(8), U256::from(1) << 224),
+        ];
+        // the second pair has no token slots
+        test_db.insert_accounts_and_storages(vec![
+            (UNI_V2_FACTORY, (Account::default(), factory_storage)),
+            (pair_address, (Account::default(), pair_storage)),
+            (broken_address, (Account::default(), vec![])),
+        ])?;
+        test_db.commit(|tx| Ok(tx.put::<tables::CanonicalHeaders>(0, B256::with_last_byte(1))?))?;
+
+        let load = |mode| {
+            UniV2Factory::load_pairs_with_progress(
+                &test_db.factory,
+                &BlockNumberOrTag::Latest,
+                UNI_V2_FACTORY,
+                &PoolFilter::new(),
+                None,
+                mode,
+                &NoopProgress,
+            )
+        };
+        assert!(load(LoadMode::Strict).is_err());
+
+        let factory = load(LoadMode::Lenient)?;
+        assert_eq!(factory.pairs.len(), 1);
+        assert_eq!(factory.pairs[0].0.address, pair_address);
+        assert_eq!(factory.failures.len(), 1);
+        assert_eq!((factory.failures[0].address, factory.failures[0].index), (broken_address, 1));
+
+        // the stream skips the broken pair the same way
+        let stream = |mode| {
+            UniV2Factory::stream_pairs(
+                Arc::new(test_db.factory.clone()),
+                BlockNumberOrTag::Latest,
+                UNI_V2_FACTORY,
+                PoolFilter::new(),
+                0,
+                2,
+                mode,
+            )
+        };
+        let chunks = stream(LoadMode::Strict).collect::<Vec<_>>().await;
+        assert!(chunks.last().unwrap().is_err());
+        let chunks = stream(LoadMode::Lenient).collect::<Vec<_>>().await.into_iter().collect::<eyre::Result<Vec<_>>>()?;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].items.len(), 1);
+        assert_eq!((chunks[0].failures[0].address, chunks[0].failures[0].index), (broken_address, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_pair() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let pair_value = U256::from_be_slice(pair_address.as_slice());
+        let factory_storage = vec![
+            StorageEntry::new(GET_PAIR.entry(&usdc).entry(&weth).slot, pair_value),
+            StorageEntry::new(GET_PAIR.entry(&weth).entry(&usdc).slot, pair_value),
+        ];
+        let pair_storage = vec![
+            StorageEntry::new(B256::with_last_byte(0), U256::from(1000)),
+            StorageEntry::new(B256::with_last_byte(6), U256::from_be_slice(usdc.as_slice())),
+            StorageEntry::new(B256::with_last_byte(7), U256::from_be_slice(weth.as_slice())),
+            // blockTimestampLast 1 | reserve1 20 | reserve0 10
+            StorageEntry::new(B256::with_last_byte(8), (U256::from(1) << 224) | (U256::from(20) << 112) | U256::from(10)),
+        ];
+        test_db.insert_accounts_and_storages(vec![
+            (UNI_V2_FACTORY, (Account::default(), factory_storage)),
+            (pair_address, (Account::default(), pair_storage)),
+        ])?;
+
+        for (token_a, token_b) in [(usdc, weth), (weth, usdc)] {
+            let (pair, state) = UniV2Factory::get_pair(test_db.factory.latest()?, UNI_V2_FACTORY, token_a, token_b)?.unwrap();
+            assert_eq!((pair.address, pair.token0, pair.token1), (pair_address, usdc, weth));
+            assert_eq!((state.reserve.reserve0.to::<u64>(), state.reserve.reserve1.to::<u64>()), (10, 20));
+            assert_eq!(state.total_supply, U256::from(1000));
+        }
+        assert!(UniV2Factory::get_pair(test_db.factory.latest()?, UNI_V2_FACTORY, usdc, Address::with_last_byte(1))?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retain_verified_pairs() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let fake_address = address!("5d27df1a6e03254e4f1218607d8e073667ffae2f");
+        let code = Account { bytecode_hash: Some(B256::with_last_byte(1)), ..Default::default() };
+        test_db.insert_accounts_and_storages(vec![(pair_address, (code, vec![])), (fake_address, (code, vec![]))])?;
+
+        let reserve = UniV2PairReserve { block_timestamp_last: 1, reserve0: Default::default(), reserve1: Default::default() };
+        let mut factory = UniV2Factory {
+            pairs: vec![
+                (UniV2Pair { address: fake_address, token0: usdc, token1: weth, creation: None }, reserve.clone()),
+                (UniV2Pair { address: pair_address, token0: usdc, token1: weth, creation: None }, reserve),
+            ],
+            ..Default::default()
+        };
+        factory.retain_verified_pairs(test_db.factory.latest()?, &PoolDeployment::uniswap_v2(&test_db.factory.latest()?)?)?;
+
+        assert_eq!(factory.pairs.len(), 1);
+        assert_eq!(factory.pairs[0].0.address, pair_address);
+        assert_eq!((factory.failures[0].address, factory.failures[0].index), (fake_address, 0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_pairs() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_addresses = [address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc"), address!("5d27df1a6e03254e4f1218607d8e073667ffae2f")];
+        insert_pairs(&test_db, &pair_addresses)?;
+
+        let mut filter = PoolFilter::new();
+        filter.block_timestamp_after(1);
+        let stream = UniV2Factory::stream_pairs(
+            Arc::new(test_db.factory.clone()),
+            BlockNumberOrTag::Latest,
+            UNI_V2_FACTORY,
+            filter,
+            0,
+            1,
+            LoadMode::Strict,
+        );
+        let chunks = stream.collect::<Vec<_>>().await.into_iter().collect::<eyre::Result<Vec<_>>>()?;
+
+        assert_eq!(chunks.len(), 2);
+        // the first pair is filtered by the block timestamp
+        assert!(chunks[0].items.is_empty());
+        assert_eq!(chunks[0].done, 1);
+        assert_eq!(chunks[1].items[0].0.address, pair_addresses[1]);
+        assert_eq!((chunks[1].done, chunks[1].total), (2, 2));
+        assert_eq!(chunks[1].block.number, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_pairs_rejects_min_age() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        insert_pairs(&test_db, &[address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc")])?;
+        let mut filter = PoolFilter::new();
+        filter.min_age(3600, 1_700_000_000);
+
+        let path = std::env::temp_dir().join(format!("dexsync_min_age_cache_{}", std::process::id()));
+        let err =
+            UniV2Factory::load_pairs(&test_db.factory, &BlockNumberOrTag::Latest, UNI_V2_FACTORY, &filter, Some(path.clone())).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::UnsupportedFilter(_))));
+        assert!(!path.exists());
+
+        let stream = UniV2Factory::stream_pairs(
+            Arc::new(test_db.factory.clone()),
+            BlockNumberOrTag::Latest,
+            UNI_V2_FACTORY,
+            filter,
+            0,
+            1,
+            LoadMode::Strict,
+        );
+        let chunks = stream.collect::<Vec<_>>().await;
+        assert_eq!(chunks.len(), 1);
+        assert!(matches!(chunks[0].as_ref().unwrap_err().downcast_ref::<DexSyncError>(), Some(DexSyncError::UnsupportedFilter(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_cache_without_creation() -> eyre::Result<()> {
+        // layout of the pairs cache before the creation was added
+        #[derive(Serialize)]
+        struct CachedPair {
+            address: Address,
+            token0: Address,
+            token1: Address,
+        }
+        #[derive(Serialize)]
+        struct Cache {
+            pairs: Vec<CachedPair>,
+        }
+
+        let path = std::env::temp_dir().join(format!("dexsync_pairs_cache_{}", std::process::id()));
+        let (address, token0, token1) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        DexSyncCache::save(&path, UNI_V2_FACTORY, Cache { pairs: vec![CachedPair { address, token0, token1 }] })?;
+
+        let cache = UniV2Factory::read_cached_pairs_if_exists(&Some(path.clone()), UNI_V2_FACTORY)?;
+        std::fs::remove_dir_all(&path)?;
+        assert_eq!(cache.pairs, vec![UniV2Pair { address, token0, token1, creation: None }]);
+
+        Ok(())
+    }
 }
@@ -1,71 +1,303 @@
+use crate::utils::{
+    read_activity, read_required_storage, read_storage, read_storage_history, read_storage_proof, DexSyncError, PoolActivity,
+};
+use alloy::rpc::types::EIP1186AccountProofResponse;
 use alloy_primitives::aliases::U112;
-use alloy_primitives::{b256, Address, B256, U160, U32};
+use alloy_primitives::{b256, keccak256, Address, BlockNumber, StorageValue, B256, U160, U256, U32, U512};
+use alloy_sol_types::SolValue;
 use eyre::eyre;
-use reth_provider::StateProvider;
+use reth_db::Database;
+use reth_provider::{HeaderProvider, ProviderError, StateProvider, StateProviderFactory};
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 
+const PAIR_TOTAL_SUPPLY: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000000");
 const PAIR_TOKEN0: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000006");
 const PAIR_TOKEN1: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000007");
 const PAIR_RESERVE: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000008");
+const PAIR_PRICE0_CUMULATIVE_LAST: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000009");
+const PAIR_PRICE1_CUMULATIVE_LAST: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000a");
+const PAIR_K_LAST: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000b");
+/// Slots of the pair read by `read_pair` and `read_pair_state`
+pub const PAIR_STATE_SLOTS: [B256; 7] =
+    [PAIR_TOTAL_SUPPLY, PAIR_TOKEN0, PAIR_TOKEN1, PAIR_RESERVE, PAIR_PRICE0_CUMULATIVE_LAST, PAIR_PRICE1_CUMULATIVE_LAST, PAIR_K_LAST];
+/// Init code hash of the Uniswap V2 pair, forks use their own
+pub const PAIR_INIT_CODE_HASH: B256 = b256!("96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f");
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UniV2Pair {
     pub address: Address,
     pub token0: Address,
     pub token1: Address,
+    /// Only known if the pair was discovered from its `PairCreated` log, not stored in the factory cache
+    #[serde(skip)]
+    pub creation: Option<PairCreation>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl UniV2Pair {
+    /// CREATE2 salt of the pair, the hash of the packed sorted tokens.
+    pub fn salt(&self) -> B256 {
+        keccak256((self.token0, self.token1).abi_encode_packed())
+    }
+
+    /// Seconds since the creation of the pair at the timestamp, `None` if the creation is unknown.
+    pub fn age_at(&self, timestamp: u64) -> Option<u64> {
+        self.creation.as_ref().map(|creation| timestamp.saturating_sub(creation.timestamp))
+    }
+}
+
+/// Block, transaction and timestamp of the `PairCreated` log of a pair.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairCreation {
+    pub block_number: BlockNumber,
+    pub block_hash: B256,
+    pub tx_hash: B256,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UniV2PairReserve {
     pub block_timestamp_last: u32,
     pub reserve0: U112,
     pub reserve1: U112,
 }
 
+impl UniV2PairReserve {
+    /// Output amount of a swap including the 0.3% fee like `UniswapV2Library.getAmountOut`.
+    pub fn get_amount_out(&self, amount_in: U256, zero_for_one: bool) -> U256 {
+        let (reserve_in, reserve_out) = if zero_for_one { (self.reserve0, self.reserve1) } else { (self.reserve1, self.reserve0) };
+        if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+            return U256::ZERO;
+        }
+        let amount_in_with_fee = U512::from(amount_in) * U512::from(997);
+        let numerator = amount_in_with_fee * U512::from(reserve_out);
+        let denominator = U512::from(reserve_in) * U512::from(1000) + amount_in_with_fee;
+        // the result is always smaller than the reserve
+        (numerator / denominator).to::<U256>()
+    }
+}
+
+/// Reserves together with the oracle and fee state of a pair.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniV2PairState {
+    pub reserve: UniV2PairReserve,
+    pub price0_cumulative_last: U256,
+    pub price1_cumulative_last: U256,
+    pub k_last: U256,
+    pub total_supply: U256,
+}
+
+impl UniV2PairState {
+    /// Cumulative prices at the timestamp like `UniswapV2OracleLibrary.currentCumulativePrices`.
+    /// The accumulators are UQ112x112 values and overflow by design.
+    pub fn cumulative_prices(&self, timestamp: u32) -> (U256, U256) {
+        let reserve = &self.reserve;
+        if reserve.block_timestamp_last == timestamp || reserve.reserve0.is_zero() || reserve.reserve1.is_zero() {
+            return (self.price0_cumulative_last, self.price1_cumulative_last);
+        }
+        let time_elapsed = U256::from(timestamp.wrapping_sub(reserve.block_timestamp_last));
+        let price0: U256 = (U256::from(reserve.reserve1) << 112) / U256::from(reserve.reserve0);
+        let price1: U256 = (U256::from(reserve.reserve0) << 112) / U256::from(reserve.reserve1);
+        (
+            self.price0_cumulative_last.wrapping_add(price0.wrapping_mul(time_elapsed)),
+            self.price1_cumulative_last.wrapping_add(price1.wrapping_mul(time_elapsed)),
+        )
+    }
+
+    /// Liquidity minted to `feeTo` on the next mint or burn like `UniswapV2Pair._mintFee`.
+    /// `kLast` is only set while the protocol fee is on, so a zero `kLast` means no fee.
+    pub fn protocol_fee_liquidity(&self) -> U256 {
+        if self.k_last.is_zero() {
+            return U256::ZERO;
+        }
+        let root_k = (U256::from(self.reserve.reserve0) * U256::from(self.reserve.reserve1)).root(2);
+        let root_k_last = self.k_last.root(2);
+        if root_k <= root_k_last {
+            return U256::ZERO;
+        }
+        let numerator = U512::from(self.total_supply) * U512::from(root_k - root_k_last);
+        let denominator = U512::from(root_k) * U512::from(5) + U512::from(root_k_last);
+        (numerator / denominator).to::<U256>()
+    }
+
+    /// Token amounts for burning the liquidity after the pending protocol fee is minted. Uses the reserves instead of the balances.
+    pub fn liquidity_value(&self, liquidity: U256) -> (U256, U256) {
+        let total_supply = U512::from(self.total_supply) + U512::from(self.protocol_fee_liquidity());
+        if total_supply.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        let amount0 = U512::from(liquidity) * U512::from(self.reserve.reserve0) / total_supply;
+        let amount1 = U512::from(liquidity) * U512::from(self.reserve.reserve1) / total_supply;
+        (amount0.to::<U256>(), amount1.to::<U256>())
+    }
+}
+
+/// Time weighted average prices as UQ112x112 values, price0 is token1 per token0.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniV2Twap {
+    pub price0_average: U256,
+    pub price1_average: U256,
+}
+
+/// TWAP between two observations of the same pair, each given with the timestamp of its block.
+pub fn compute_pair_twap(
+    start: &UniV2PairState,
+    start_timestamp: u32,
+    end: &UniV2PairState,
+    end_timestamp: u32,
+) -> eyre::Result<UniV2Twap> {
+    let time_elapsed = end_timestamp.wrapping_sub(start_timestamp);
+    if time_elapsed == 0 {
+        return Err(eyre!("TWAP_PERIOD_EMPTY"));
+    }
+    let (start_price0, start_price1) = start.cumulative_prices(start_timestamp);
+    let (end_price0, end_price1) = end.cumulative_prices(end_timestamp);
+    Ok(UniV2Twap {
+        price0_average: end_price0.wrapping_sub(start_price0) / U256::from(time_elapsed),
+        price1_average: end_price1.wrapping_sub(start_price1) / U256::from(time_elapsed),
+    })
+}
+
+/// All reserve changes of a pair in a block range. Each change holds the reserves after the block.
+#[derive(Debug)]
+pub struct UniV2PairReserveHistory {
+    pub address: Address,
+    pub initial_reserve: UniV2PairReserve,
+    pub changes: Vec<(BlockNumber, UniV2PairReserve)>,
+}
+
 pub fn read_pair<T: StateProvider>(provider: T, pair_address: Address) -> eyre::Result<UniV2Pair> {
-    let token0 = match provider.storage(pair_address, PAIR_TOKEN0) {
-        Ok(storage_value) => match storage_value {
-            None => return Err(eyre!("STORAGE_SLOT_NOT_FOUND token0, {:#?}", pair_address)),
-            Some(value) => Address::from(U160::from(value)),
-        },
-        Err(e) => return Err(eyre!(e)),
-    };
+    let token0 = Address::from(U160::from(read_required_storage(&provider, pair_address, PAIR_TOKEN0)?));
+    let token1 = Address::from(U160::from(read_required_storage(&provider, pair_address, PAIR_TOKEN1)?));
+    Ok(UniV2Pair { address: pair_address, token0, token1, creation: None })
+}
 
-    let token1 = match provider.storage(pair_address, PAIR_TOKEN1) {
-        Ok(storage_value) => match storage_value {
-            None => return Err(eyre!("STORAGE_SLOT_NOT_FOUND token1, {:#?}", pair_address)),
-            Some(value) => Address::from(U160::from(value)),
-        },
-        Err(e) => return Err(eyre!(e)),
-    };
-    Ok(UniV2Pair { address: pair_address, token0, token1 })
+/// Address of the pair of the tokens like `UniswapV2Library.pairFor`, the tokens may be given in any order.
+pub fn compute_pair_address(factory: Address, init_code_hash: B256, token_a: Address, token_b: Address) -> eyre::Result<Address> {
+    if token_a == token_b {
+        return Err(DexSyncError::InvalidTokenPair { token_a, token_b, reason: "identical addresses".to_string() }.into());
+    }
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    if token0.is_zero() {
+        return Err(DexSyncError::InvalidTokenPair { token_a, token_b, reason: "zero address".to_string() }.into());
+    }
+    Ok(factory.create2(keccak256((token0, token1).abi_encode_packed()), init_code_hash))
+}
+
+/// Read a pair if the address holds a pair. Returns `None` if the token0 slot is empty.
+pub fn read_pair_if_exists<T: StateProvider>(provider: T, pair_address: Address) -> eyre::Result<Option<UniV2Pair>> {
+    match read_storage(&provider, pair_address, PAIR_TOKEN0)? {
+        None => Ok(None),
+        Some(_) => Ok(Some(read_pair(provider, pair_address)?)),
+    }
 }
 
 pub fn read_pair_reserves<T: StateProvider>(provider: T, pair_address: Address) -> eyre::Result<UniV2PairReserve> {
-    let (block_timestamp_last, reserve1, reserve0) = match provider.storage(pair_address, PAIR_RESERVE) {
-        Ok(storage_value) => match storage_value {
-            None => (0, U112::ZERO, U112::ZERO), // pair not initialized
-            Some(value) => {
-                let bytes = value.to_be_bytes_vec();
-                let block_timestamp_last = U32::from_be_slice(&bytes[0..4]);
-                let reserve1 = U112::from_be_slice(&bytes[4..18]);
-                let reserve0 = U112::from_be_slice(&bytes[18..32]);
-                (block_timestamp_last.to::<u32>(), reserve1, reserve0)
-            }
-        },
-        Err(e) => return Err(eyre!(e)),
+    // zero if pair not initialized
+    Ok(decode_pair_reserves(read_storage(&provider, pair_address, PAIR_RESERVE)?.unwrap_or_default()))
+}
+
+/// Proof of the reserves slot of the pair, see `read_storage_proof`.
+pub fn read_pair_reserves_proof<T: StateProvider>(provider: T, pair_address: Address) -> Result<EIP1186AccountProofResponse, DexSyncError> {
+    read_storage_proof(provider, pair_address, &[PAIR_RESERVE])
+}
+
+/// Read reserves, price accumulators, `kLast` and `totalSupply` of a pair. Unset slots are zero.
+pub fn read_pair_state<T: StateProvider>(provider: T, pair_address: Address) -> eyre::Result<UniV2PairState> {
+    let read_slot = |slot| read_storage(&provider, pair_address, slot).map(Option::unwrap_or_default);
+    let reserve = decode_pair_reserves(read_slot(PAIR_RESERVE)?);
+    let price0_cumulative_last = read_slot(PAIR_PRICE0_CUMULATIVE_LAST)?;
+    let price1_cumulative_last = read_slot(PAIR_PRICE1_CUMULATIVE_LAST)?;
+    let k_last = read_slot(PAIR_K_LAST)?;
+    let total_supply = read_slot(PAIR_TOTAL_SUPPLY)?;
+    Ok(UniV2PairState { reserve, price0_cumulative_last, price1_cumulative_last, k_last, total_supply })
+}
+
+/// TWAP of a pair between the state after `start_block` and the state after `end_block` using the block timestamps.
+pub fn read_pair_twap<P: StateProviderFactory + HeaderProvider>(
+    provider_factory: &P,
+    pair_address: Address,
+    start_block: BlockNumber,
+    end_block: BlockNumber,
+) -> eyre::Result<UniV2Twap> {
+    let read_observation = |block_number: BlockNumber| -> eyre::Result<(UniV2PairState, u32)> {
+        let header = provider_factory.header_by_number(block_number)?.ok_or(ProviderError::HeaderNotFound(block_number.into()))?;
+        let state = read_pair_state(provider_factory.history_by_block_number(block_number)?, pair_address)?;
+        // the pair stores the timestamp modulo 2**32
+        Ok((state, header.timestamp as u32))
     };
+    let (start, start_timestamp) = read_observation(start_block)?;
+    let (end, end_timestamp) = read_observation(end_block)?;
+    compute_pair_twap(&start, start_timestamp, &end, end_timestamp)
+}
 
-    Ok(UniV2PairReserve { block_timestamp_last, reserve0, reserve1 })
+/// Decode the packed reserve slot `(uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)`.
+pub fn decode_pair_reserves(value: StorageValue) -> UniV2PairReserve {
+    let bytes = value.to_be_bytes_vec();
+    let block_timestamp_last = U32::from_be_slice(&bytes[0..4]);
+    let reserve1 = U112::from_be_slice(&bytes[4..18]);
+    let reserve0 = U112::from_be_slice(&bytes[18..32]);
+    UniV2PairReserve { block_timestamp_last: block_timestamp_last.to::<u32>(), reserve0, reserve1 }
+}
+
+/// Creation block and last reserve change of the pair from the history indices, e.g. to skip pairs inactive for 90 days.
+pub fn read_pair_activity<DB: Database>(db: &DB, pair_address: Address) -> eyre::Result<PoolActivity> {
+    read_activity(db, pair_address, PAIR_RESERVE)
+}
+
+/// Read all reserve changes of the pairs in the block range by walking the storage history index.
+pub fn read_pairs_reserves_history<DB: Database>(
+    db: &DB,
+    pair_addresses: &[Address],
+    block_range: RangeInclusive<BlockNumber>,
+) -> eyre::Result<Vec<UniV2PairReserveHistory>> {
+    let mut result = Vec::with_capacity(pair_addresses.len());
+    for pair_address in pair_addresses {
+        let history = read_storage_history(db, *pair_address, PAIR_RESERVE, block_range.clone())?;
+        let changes = history.changes.into_iter().map(|change| (change.block_number, decode_pair_reserves(change.value))).collect();
+        result.push(UniV2PairReserveHistory {
+            address: *pair_address,
+            initial_reserve: decode_pair_reserves(history.initial_value),
+            changes,
+        });
+    }
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::univ2::UNI_V2_FACTORY;
+    use crate::utils::DexSyncError;
     use alloy_primitives::{address, U256};
+    use reth_db::models::storage_sharded_key::StorageShardedKey;
+    use reth_db::models::BlockNumberAddress;
+    use reth_db::transaction::DbTxMut;
+    use reth_db::{tables, BlockNumberList};
     use reth_primitives::{Account, StorageEntry};
     use reth_stages::test_utils::TestStageDB;
 
+    #[test]
+    fn test_compute_pair_address() -> eyre::Result<()> {
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let expected_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+
+        assert_eq!(compute_pair_address(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH, usdc, weth)?, expected_address);
+        assert_eq!(compute_pair_address(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH, weth, usdc)?, expected_address);
+        let err = compute_pair_address(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH, weth, weth).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidTokenPair { .. })));
+        let err = compute_pair_address(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH, weth, Address::ZERO).unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidTokenPair { reason, .. }) if reason == "zero address")
+        );
+
+        let pair = UniV2Pair { address: expected_address, token0: usdc, token1: weth, creation: None };
+        assert_eq!(UNI_V2_FACTORY.create2(pair.salt(), PAIR_INIT_CODE_HASH), expected_address);
+
+        Ok(())
+    }
+
     #[test]
     fn test_read_pair() -> eyre::Result<()> {
         let test_db = TestStageDB::default();
@@ -93,6 +325,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_pair_errors() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let other_address = address!("5d27df1a6e03254e4f1218607d8e073667ffae2f");
+        test_db.insert_accounts_and_storages(vec![(pair_address, (Account::default(), vec![]))])?;
+
+        let err = read_pair(test_db.factory.latest()?, pair_address).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<DexSyncError>(),
+            Some(DexSyncError::MissingSlot { address, slot }) if *address == pair_address && *slot == PAIR_TOKEN0
+        ));
+        let err = read_pair(test_db.factory.latest()?, other_address).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::MissingContract(address)) if *address == other_address));
+
+        Ok(())
+    }
+
     #[test]
     fn test_read_pair_reserves() -> eyre::Result<()> {
         let test_db = TestStageDB::default();
@@ -117,4 +368,122 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_amount_out() {
+        let reserve =
+            UniV2PairReserve { block_timestamp_last: 0, reserve0: U112::from(5_000_000_000u128), reserve1: U112::from(10u128.pow(21)) };
+        // 1000 USDC for WETH
+        assert_eq!(reserve.get_amount_out(U256::from(1_000_000_000u128), true), U256::from(166249791562447890611u128));
+        assert_eq!(reserve.get_amount_out(U256::ZERO, true), U256::ZERO);
+        let empty = UniV2PairReserve { block_timestamp_last: 0, reserve0: U112::ZERO, reserve1: U112::ZERO };
+        assert_eq!(empty.get_amount_out(U256::from(1), false), U256::ZERO);
+    }
+
+    #[test]
+    fn test_read_pair_state() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        test_db.insert_accounts_and_storages(vec![(
+            pair_address,
+            (
+                Account::default(),
+                vec![
+                    StorageEntry::new(PAIR_TOTAL_SUPPLY, U256::from(1000)),
+                    StorageEntry::new(PAIR_PRICE0_CUMULATIVE_LAST, U256::from(11)),
+                    StorageEntry::new(PAIR_PRICE1_CUMULATIVE_LAST, U256::from(22)),
+                    StorageEntry::new(PAIR_K_LAST, U256::from(4_000_000)),
+                ],
+            ),
+        )])?;
+
+        let state = read_pair_state(test_db.factory.latest()?, pair_address)?;
+        assert_eq!(state.reserve.reserve0, U112::ZERO);
+        assert_eq!(state.total_supply, U256::from(1000));
+        assert_eq!(state.price0_cumulative_last, U256::from(11));
+        assert_eq!(state.price1_cumulative_last, U256::from(22));
+        assert_eq!(state.k_last, U256::from(4_000_000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_protocol_fee_liquidity() {
+        let mut state = UniV2PairState {
+            reserve: UniV2PairReserve { block_timestamp_last: 0, reserve0: U112::from(3000), reserve1: U112::from(3000) },
+            price0_cumulative_last: U256::ZERO,
+            price1_cumulative_last: U256::ZERO,
+            k_last: U256::from(2000 * 2000),
+            total_supply: U256::from(2000),
+        };
+        // rootK 3000, rootKLast 2000: 2000 * 1000 / (3000 * 5 + 2000)
+        assert_eq!(state.protocol_fee_liquidity(), U256::from(117));
+        // 117 minted on top of the total supply of 2000
+        assert_eq!(state.liquidity_value(U256::from(2117)), (U256::from(3000), U256::from(3000)));
+
+        state.k_last = U256::ZERO;
+        assert_eq!(state.protocol_fee_liquidity(), U256::ZERO);
+        assert_eq!(state.liquidity_value(U256::from(1000)), (U256::from(1500), U256::from(1500)));
+    }
+
+    #[test]
+    fn test_compute_pair_twap() -> eyre::Result<()> {
+        let q112 = U256::from(1) << 112;
+        // price0 of 2 since timestamp 100, no update until the end observation
+        let start = UniV2PairState {
+            reserve: UniV2PairReserve { block_timestamp_last: 100, reserve0: U112::from(1000), reserve1: U112::from(2000) },
+            price0_cumulative_last: U256::ZERO,
+            price1_cumulative_last: U256::ZERO,
+            k_last: U256::ZERO,
+            total_supply: U256::ZERO,
+        };
+        let end = start.clone();
+
+        let twap = compute_pair_twap(&start, 110, &end, 170)?;
+        assert_eq!(twap.price0_average, q112 * U256::from(2));
+        assert_eq!(twap.price1_average, q112 / U256::from(2));
+
+        // accumulators overflowing in between
+        let mut start = start;
+        start.price0_cumulative_last = U256::MAX - q112 + U256::from(1);
+        let mut end = start.clone();
+        end.price0_cumulative_last = q112 * U256::from(19);
+        end.reserve.block_timestamp_last = 110;
+        let twap = compute_pair_twap(&start, 100, &end, 110)?;
+        assert_eq!(twap.price0_average, q112 * U256::from(2));
+
+        assert!(compute_pair_twap(&start, 100, &start, 100).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_pairs_reserves_history() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let reserve_before = U256::from_be_slice(b256!("6700f0a30000000003c5512b85fc28d1721e0000000000000000272e698defb8").as_slice());
+        let reserve_after = U256::from_be_slice(b256!("6700f0af0000000003c5512b85fc28d1721e0000000000000000272e698defb9").as_slice());
+
+        test_db.insert_accounts_and_storages(vec![(
+            pair_address,
+            (Account::default(), vec![StorageEntry::new(PAIR_RESERVE, reserve_after)]),
+        )])?;
+        test_db.commit(|tx| {
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pair_address, PAIR_RESERVE, u64::MAX),
+                BlockNumberList::new_pre_sorted([100]),
+            )?;
+            tx.put::<tables::StorageChangeSets>(BlockNumberAddress((100, pair_address)), StorageEntry::new(PAIR_RESERVE, reserve_before))?;
+            Ok(())
+        })?;
+
+        let history = read_pairs_reserves_history(test_db.factory.db_ref(), &[pair_address], 50..=150)?;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].initial_reserve, decode_pair_reserves(reserve_before));
+        assert_eq!(history[0].changes, vec![(100, decode_pair_reserves(reserve_after))]);
+        assert_eq!(history[0].changes[0].1.reserve0, U112::from(43080292888505u128));
+
+        Ok(())
+    }
 }
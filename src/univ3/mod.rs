@@ -1,11 +1,29 @@
 mod ticks;
+mod univ3_factory;
+mod univ3_math;
 mod univ3_pool;
+mod univ3_pool_storage;
 mod univ3_position;
 
 use alloy_primitives::{address, Address};
-pub use ticks::SLOT_KEYS_TO_TICKS;
-pub use univ3_pool::{read_slot0, read_tick, TickInfo, Univ3Pool, Univ3Slot0};
-pub use univ3_position::{read_univ3_position_pools, UniV3PositionManager};
+pub use ticks::{tick_index, tick_storage_key, TickIndex};
+pub use univ3_factory::{get_univ3_pool, read_fee_amount_tick_spacing, read_fee_tier, UniV3FeeTier};
+pub use univ3_math::{
+    get_amounts_for_liquidity, get_fee_growth_inside, get_fees_owed, get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, is_valid_tick,
+    swap_exact_input, tick_spacing_to_max_liquidity_per_tick, TickLiquidityNet, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK,
+};
+pub use univ3_pool::{
+    decode_liquidity, decode_protocol_fees, decode_slot0, decode_tick_info, read_fee_growth_global, read_fee_growth_inside, read_liquidity,
+    read_pool_activity, read_pool_globals, read_pool_position, read_pool_positions, read_pool_state, read_pools_history,
+    read_position_value, read_slot0, read_slot0_proof, read_tick, read_tick_bitmap_word, read_ticks, read_ticks_from_storage,
+    tick_spacing_from_fee, ticks_from_storage_entries, PositionInfo, PositionValue, TickInfo, Univ3Pool, Univ3PoolChange, Univ3PoolGlobals,
+    Univ3PoolHistory, Univ3PoolPosition, Univ3PoolState, Univ3Slot0,
+};
+pub use univ3_pool_storage::{decode_observation, read_pool_state_from_storage, Observation, Univ3PoolStorage};
+pub use univ3_position::{
+    group_positions_by_owner, group_positions_by_pool, read_nft_positions, read_pool_key, read_pool_key_by_address,
+    read_univ3_position_pools, NftPosition, PoolKey, UniV3PositionManager, POOL_INIT_CODE_HASH,
+};
 
 pub const UNI_V3_FACTORY: Address = address!("1F98431c8aD98523631AE4a59f267346ea31F984");
 pub const UNI_V3_POSITION_MANAGER: Address = address!("c36442b4a4522e871399cd717abdd847ab11fe88");
@@ -1,56 +1,265 @@
+use crate::univ3::univ3_math::{MAX_TICK, MIN_TICK};
+use crate::univ3::univ3_pool::TICKS;
+use crate::utils::DexSyncError;
 use alloy_primitives::aliases::I24;
-use alloy_primitives::{b256, keccak256, B256};
-use alloy_sol_types::SolValue;
+use alloy_primitives::{b256, Address, B256};
+use eyre::eyre;
 use lazy_static::lazy_static;
+use memmap2::Mmap;
 use std::collections::HashMap;
-
-const MIN_TICK: i32 = -887272;
-const MAX_TICK: i32 = -MIN_TICK;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 pub const TICKS_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000005");
 
-macro_rules! fill_slot_keys_ticks_map {
-    ($slot:expr, $tick_spacing:expr) => {{
-        let mut map = HashMap::new();
-        for tick in (0..=MAX_TICK).step_by($tick_spacing) {
-            let storage_key: B256 = keccak256((I24::try_from(tick).unwrap(), TICKS_SLOT).abi_encode());
-            map.insert(storage_key, tick);
-            let storage_key: B256 = keccak256((I24::try_from(-tick).unwrap(), TICKS_SLOT).abi_encode());
-            map.insert(storage_key, -tick);
+const INDEX_MAGIC: &[u8; 4] = b"DSTI";
+const INDEX_VERSION: u32 = 1;
+const HEADER_LEN: usize = 16;
+// u64 key prefix and i32 tick, little endian
+const RECORD_LEN: usize = 12;
+
+lazy_static! {
+    static ref TICK_INDEXES: Mutex<HashMap<i32, Arc<TickIndex>>> = Mutex::new(HashMap::new());
+}
+
+/// Storage key of the first slot of a tick in the pool's `ticks` mapping.
+pub fn tick_storage_key(tick: I24) -> B256 {
+    TICKS.entry_slot(&tick)
+}
+
+/// Storage key of a tick of the index, `None` for a tick outside of the `int24` range, e.g. of a corrupt index file.
+fn index_storage_key(tick: i32) -> Option<B256> {
+    I24::try_from(tick).ok().map(tick_storage_key)
+}
+
+enum TickIndexData {
+    Memory(Vec<u8>),
+    Mapped(Mmap),
+}
+
+/// Reverse index from the storage key of a tick to the tick for a single tick spacing.
+/// Records are sorted by the first 8 bytes of the storage key, a match is verified by hashing the tick.
+pub struct TickIndex {
+    tick_spacing: i32,
+    data: TickIndexData,
+}
+
+impl TickIndex {
+    /// Hash all usable ticks of the tick spacing. A tick spacing that is not positive is an invalid layout, without the
+    /// address of a contract.
+    pub fn build(tick_spacing: i32) -> eyre::Result<Self> {
+        if tick_spacing <= 0 {
+            return Err(DexSyncError::invalid_layout(Address::ZERO, format!("tick spacing {}", tick_spacing)).into());
+        }
+        let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
+        let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
+
+        let mut records: Vec<(u64, i32)> = (min_tick..=max_tick)
+            .step_by(tick_spacing as usize)
+            .filter_map(|tick| Some((u64::from_be_bytes(index_storage_key(tick)?[0..8].try_into().unwrap()), tick)))
+            .collect();
+        records.sort_unstable();
+
+        let mut data = Vec::with_capacity(HEADER_LEN + records.len() * RECORD_LEN);
+        data.extend_from_slice(INDEX_MAGIC);
+        data.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        data.extend_from_slice(&tick_spacing.to_le_bytes());
+        data.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        for (prefix, tick) in records {
+            data.extend_from_slice(&prefix.to_le_bytes());
+            data.extend_from_slice(&tick.to_le_bytes());
+        }
+        Ok(TickIndex { tick_spacing, data: TickIndexData::Memory(data) })
+    }
+
+    /// Memory map the index file of the tick spacing in the directory. The file is built and written first if it is missing or invalid.
+    pub fn load_or_build(tick_spacing: i32, path: &Path) -> eyre::Result<Self> {
+        let file_path = path.join(format!("ticks_{}.idx", tick_spacing));
+        if let Some(index) = Self::load(tick_spacing, &file_path)? {
+            return Ok(index);
+        }
+
+        let index = Self::build(tick_spacing)?;
+        if !path.exists() {
+            fs::create_dir_all(path)?;
+        }
+        // write to a temporary file first, a concurrent reader never sees a partial index
+        let tmp_path = file_path.with_extension(format!("idx.{}", std::process::id()));
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(index.bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &file_path)?;
+
+        Ok(Self::load(tick_spacing, &file_path)?.unwrap_or(index))
+    }
+
+    fn load(tick_spacing: i32, file_path: &Path) -> eyre::Result<Option<Self>> {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        let file = fs::File::open(file_path)?;
+        // SAFETY: the index file is only replaced by rename, never modified in place
+        let mmap = unsafe { Mmap::map(&file)? };
+        let index = TickIndex { tick_spacing, data: TickIndexData::Mapped(mmap) };
+        Ok(index.is_valid().then_some(index))
+    }
+
+    pub fn tick_spacing(&self) -> i32 {
+        self.tick_spacing
+    }
+
+    pub fn len(&self) -> usize {
+        (self.bytes().len() - HEADER_LEN) / RECORD_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The tick of a storage key of the `ticks` mapping. Returns `None` for any other storage key.
+    pub fn tick(&self, storage_key: &B256) -> Option<i32> {
+        let prefix = u64::from_be_bytes(storage_key[0..8].try_into().unwrap());
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.record(mid).0 < prefix {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
         }
+        (low..self.len())
+            .map(|idx| self.record(idx))
+            .take_while(|(record_prefix, _)| *record_prefix == prefix)
+            .map(|(_, tick)| tick)
+            .find(|tick| index_storage_key(*tick) == Some(*storage_key))
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.data {
+            TickIndexData::Memory(data) => data,
+            TickIndexData::Mapped(mmap) => mmap,
+        }
+    }
 
-        map
-    }};
+    fn record(&self, idx: usize) -> (u64, i32) {
+        let offset = HEADER_LEN + idx * RECORD_LEN;
+        let bytes = &self.bytes()[offset..offset + RECORD_LEN];
+        (u64::from_le_bytes(bytes[0..8].try_into().unwrap()), i32::from_le_bytes(bytes[8..12].try_into().unwrap()))
+    }
+
+    fn is_valid(&self) -> bool {
+        let bytes = self.bytes();
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != INDEX_MAGIC {
+            return false;
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let tick_spacing = i32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        let count = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        version == INDEX_VERSION && tick_spacing == self.tick_spacing && bytes.len() == HEADER_LEN + count * RECORD_LEN
+    }
 }
 
-lazy_static! {
-    pub static ref SLOT_KEYS_TO_TICKS: HashMap<B256, i32> = fill_slot_keys_ticks_map!(TICKS_SLOT, 1);
+/// The tick index of a tick spacing, built on first use and shared afterward. With a path the index is persisted and memory mapped,
+/// replacing an in-memory index cached by an earlier call without path. The index is built without holding the cache lock.
+pub fn tick_index(tick_spacing: i32, path: Option<&Path>) -> eyre::Result<Arc<TickIndex>> {
+    let cached = |indexes: &HashMap<i32, Arc<TickIndex>>| {
+        indexes.get(&tick_spacing).filter(|index| path.is_none() || matches!(index.data, TickIndexData::Mapped(_))).cloned()
+    };
+    if let Some(index) = cached(&*TICK_INDEXES.lock().map_err(|_| eyre!("TICK_INDEX_LOCK_POISONED"))?) {
+        return Ok(index);
+    }
+
+    let index = match path {
+        Some(path) => TickIndex::load_or_build(tick_spacing, path)?,
+        None => TickIndex::build(tick_spacing)?,
+    };
+
+    let mut indexes = TICK_INDEXES.lock().map_err(|_| eyre!("TICK_INDEX_LOCK_POISONED"))?;
+    // keep an index a concurrent call cached meanwhile
+    if let Some(index) = cached(&indexes) {
+        return Ok(index);
+    }
+    let index = Arc::new(index);
+    indexes.insert(tick_spacing, index.clone());
+    Ok(index)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+
+    fn key(tick: i32) -> B256 {
+        index_storage_key(tick).unwrap()
+    }
 
     fn num_ticks(tick_spacing: i32) -> u32 {
         let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
         let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
-        let num_ticks = ((max_tick - min_tick) / tick_spacing) as u32 + 1;
-        num_ticks
+        ((max_tick - min_tick) / tick_spacing) as u32 + 1
     }
 
     #[test]
-    fn test_fill_ticks() {
+    fn test_tick_index() -> eyre::Result<()> {
         // fee => tick, 10000 => 200, 3000 => 60, 500 => 10, 100 => 1
 
-        let tick_spacing: usize = 1;
-        let num_ticks = num_ticks(tick_spacing as i32);
+        let tick_spacing = 60;
+        let index = TickIndex::build(tick_spacing)?;
+        assert_eq!(index.tick(&b256!("87361ea236b1c1a4b101e72bd6c912613e5b68034f169d3f702e04d520b95e40")), Some(1740));
+        assert_eq!(index.tick(&b256!("f0d9527cb167031b75168a62435c989786c3c280f4295693c5c862fd764aad88")), Some(3180));
+        assert_eq!(index.tick(&b256!("ce86dc00f32e6e2f4f3dc9f672a068ead5635d305cd72b916224201b344d2c6e")), Some(-345420));
+        assert_eq!(index.tick(&key(-887220)), Some(-887220));
+        // tick not on the spacing and other slots
+        assert_eq!(index.tick(&key(1)), None);
+        assert_eq!(index.tick(&TICKS_SLOT), None);
+        assert_eq!(index_storage_key(MAX_TICK), Some(tick_storage_key(I24::try_from(MAX_TICK)?)));
+        assert_eq!(index_storage_key(1 << 23), None);
+
+        assert_eq!(num_ticks(tick_spacing), index.len() as u32);
+
+        let err = TickIndex::build(0).err().unwrap();
+        assert!(matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidLayout { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick_index_load_or_build() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("rethdb_dexsync_tick_index_{}", std::process::id()));
+
+        let built = TickIndex::load_or_build(200, &path)?;
+        assert!(matches!(built.data, TickIndexData::Mapped(_)));
+        let loaded = TickIndex::load_or_build(200, &path)?;
+        assert_eq!(loaded.len(), num_ticks(200) as usize);
+        assert_eq!(loaded.tick(&key(-345400)), Some(-345400));
+
+        // a file of another spacing is not accepted
+        fs::copy(path.join("ticks_200.idx"), path.join("ticks_100.idx"))?;
+        let rebuilt = TickIndex::load_or_build(100, &path)?;
+        assert_eq!(rebuilt.len(), num_ticks(100) as usize);
+
+        fs::remove_dir_all(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick_index_cache() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("rethdb_dexsync_tick_index_cache_{}", std::process::id()));
+
+        let memory = tick_index(2000, None)?;
+        assert!(matches!(memory.data, TickIndexData::Memory(_)));
+        assert!(Arc::ptr_eq(&memory, &tick_index(2000, None)?));
 
-        let map: HashMap<B256, i32> = fill_slot_keys_ticks_map!(TICKS_SLOT, tick_spacing);
-        assert_eq!(*map.get(&b256!("87361ea236b1c1a4b101e72bd6c912613e5b68034f169d3f702e04d520b95e40")).unwrap(), 1740);
-        assert_eq!(*map.get(&b256!("f0d9527cb167031b75168a62435c989786c3c280f4295693c5c862fd764aad88")).unwrap(), 3180);
-        assert_eq!(*map.get(&b256!("ce86dc00f32e6e2f4f3dc9f672a068ead5635d305cd72b916224201b344d2c6e")).unwrap(), -345420);
+        // a later call with a path persists and maps the index
+        let mapped = tick_index(2000, Some(&path))?;
+        assert!(matches!(mapped.data, TickIndexData::Mapped(_)));
+        assert!(path.join("ticks_2000.idx").exists());
+        assert!(Arc::ptr_eq(&mapped, &tick_index(2000, None)?));
+        assert!(Arc::ptr_eq(&mapped, &tick_index(2000, Some(&path))?));
 
-        assert_eq!(num_ticks, map.len() as u32);
+        fs::remove_dir_all(&path)?;
+        Ok(())
     }
 }
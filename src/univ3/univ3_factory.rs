@@ -0,0 +1,147 @@
+use crate::univ3::univ3_math::tick_spacing_to_max_liquidity_per_tick;
+use crate::univ3::univ3_pool::{read_pool_state, Univ3Pool, Univ3PoolState};
+use crate::utils::{read_storage, DexSyncError, Field, Mapping};
+use alloy_primitives::aliases::{I24, U24};
+use alloy_primitives::{b256, Address, B256};
+use reth_provider::StateProvider;
+use serde::{Deserialize, Serialize};
+
+const FEE_AMOUNT_TICK_SPACING: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000004");
+/// `mapping(uint24 => int24) feeAmountTickSpacing`
+const FEE_AMOUNT_TICK_SPACING_MAP: Mapping<U24, Field<I24>> = Mapping::new(FEE_AMOUNT_TICK_SPACING);
+const GET_POOL_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000005");
+/// `mapping(address => mapping(address => mapping(uint24 => address))) getPool`, set for both token orders
+const GET_POOL: Mapping<Address, Mapping<Address, Mapping<U24, Field<Address>>>> = Mapping::new(GET_POOL_SLOT);
+
+/// A fee tier enabled in the factory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniV3FeeTier {
+    pub fee: U24,
+    pub tick_spacing: i32,
+    pub max_liquidity_per_tick: u128,
+}
+
+/// Read the tick spacing of a fee from the factory's `feeAmountTickSpacing` mapping. Returns `None` if the fee is not enabled.
+pub fn read_fee_amount_tick_spacing<T: StateProvider>(provider: T, factory: Address, fee: U24) -> eyre::Result<Option<i32>> {
+    let tick_spacing = FEE_AMOUNT_TICK_SPACING_MAP.entry(&fee);
+    let Some(value) = read_storage(&provider, factory, tick_spacing.slot)? else {
+        return Ok(None);
+    };
+    let tick_spacing = tick_spacing.decode(value).as_i32();
+    Ok((tick_spacing > 0).then_some(tick_spacing))
+}
+
+/// Read a fee tier with its tick spacing and the derived max liquidity per tick.
+pub fn read_fee_tier<T: StateProvider>(provider: T, factory: Address, fee: U24) -> eyre::Result<Option<UniV3FeeTier>> {
+    let Some(tick_spacing) = read_fee_amount_tick_spacing(provider, factory, fee)? else {
+        return Ok(None);
+    };
+    Ok(Some(UniV3FeeTier { fee, tick_spacing, max_liquidity_per_tick: tick_spacing_to_max_liquidity_per_tick(tick_spacing) }))
+}
+
+/// Look up the pool of two tokens and a fee in the factory's `getPool` mapping and read its state with all initialized ticks,
+/// without loading the other pools. Returns `None` if the factory has no such pool or the pool is not initialized.
+pub fn get_univ3_pool<T: StateProvider>(
+    provider: T,
+    factory: Address,
+    token_a: Address,
+    token_b: Address,
+    fee: U24,
+) -> eyre::Result<Option<(Univ3Pool, Univ3PoolState)>> {
+    let address = GET_POOL.entry(&token_a).entry(&token_b).entry(&fee).read(&provider, factory)?;
+    if address.is_zero() {
+        return Ok(None);
+    }
+    let Some(tick_spacing) = read_fee_amount_tick_spacing(&provider, factory, fee)? else {
+        return Err(DexSyncError::invalid_layout(factory, format!("pool {:#?} of fee {} not enabled", address, fee)).into());
+    };
+    let Some(state) = read_pool_state(&provider, address, fee, tick_spacing)? else {
+        return Ok(None);
+    };
+    let (token0, token1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
+    Ok(Some((Univ3Pool { address, token0, token1, fee }, state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ3::UNI_V3_FACTORY;
+    use alloy_primitives::{address, keccak256, U128, U256};
+    use alloy_sol_types::SolValue;
+    use reth_primitives::{Account, StorageEntry};
+    use reth_stages::test_utils::TestStageDB;
+
+    #[test]
+    fn test_read_fee_tier() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        test_db.insert_accounts_and_storages(vec![(
+            UNI_V3_FACTORY,
+            (
+                Account::default(),
+                vec![StorageEntry::new(keccak256((U24::from(3000), FEE_AMOUNT_TICK_SPACING).abi_encode()), U256::from(60))],
+            ),
+        )])?;
+
+        let fee_tier = read_fee_tier(test_db.factory.latest()?, UNI_V3_FACTORY, U24::from(3000))?.unwrap();
+        assert_eq!(fee_tier.tick_spacing, 60);
+        assert_eq!(fee_tier.max_liquidity_per_tick, 11505743598341114571880798222544994);
+
+        assert_eq!(read_fee_amount_tick_spacing(test_db.factory.latest()?, UNI_V3_FACTORY, U24::from(2500))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_univ3_pool() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pool_address = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        let fee = U24::from(500);
+        let pool_value = U256::from_be_slice(pool_address.as_slice());
+
+        test_db.insert_accounts_and_storages(vec![
+            (
+                UNI_V3_FACTORY,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(FEE_AMOUNT_TICK_SPACING_MAP.entry(&fee).slot, U256::from(10)),
+                        StorageEntry::new(GET_POOL.entry(&usdc).entry(&weth).entry(&fee).slot, pool_value),
+                        StorageEntry::new(GET_POOL.entry(&weth).entry(&usdc).entry(&fee).slot, pool_value),
+                        // a pool of a fee that is not enabled
+                        StorageEntry::new(GET_POOL.entry(&usdc).entry(&weth).entry(&U24::from(100)).slot, pool_value),
+                    ],
+                ),
+            ),
+            (
+                pool_address,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(
+                            B256::ZERO,
+                            U256::from_be_slice(b256!("00010002d302d301800307320000000000004f96a4fc64ac43f93680a947bbda").as_slice()),
+                        ),
+                        StorageEntry::new(B256::with_last_byte(4), U256::from(1000)),
+                    ],
+                ),
+            ),
+        ])?;
+
+        for (token_a, token_b) in [(usdc, weth), (weth, usdc)] {
+            let (pool, state) = get_univ3_pool(test_db.factory.latest()?, UNI_V3_FACTORY, token_a, token_b, fee)?.unwrap();
+            assert_eq!((pool.address, pool.token0, pool.token1), (pool_address, usdc, weth));
+            assert_eq!((state.tick_spacing, state.liquidity), (10, U128::from(1000)));
+            assert_eq!(state.slot0.tick, I24::from_dec_str("198450")?);
+        }
+        assert!(get_univ3_pool(test_db.factory.latest()?, UNI_V3_FACTORY, usdc, weth, U24::from(3000))?.is_none());
+        let err = get_univ3_pool(test_db.factory.latest()?, UNI_V3_FACTORY, usdc, weth, U24::from(100)).unwrap_err();
+        assert!(
+            matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidLayout { address, .. }) if *address == UNI_V3_FACTORY)
+        );
+
+        Ok(())
+    }
+}
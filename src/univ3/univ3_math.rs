@@ -0,0 +1,483 @@
+use alloy_primitives::ruint::UintTryFrom;
+use alloy_primitives::{uint, U256, U512};
+use eyre::eyre;
+use std::collections::BTreeMap;
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+pub const MIN_SQRT_RATIO: U256 = uint!(4295128739_U256);
+pub const MAX_SQRT_RATIO: U256 = uint!(1461446703485210103287273052203988822378723970342_U256);
+
+const Q96: U256 = uint!(0x1000000000000000000000000_U256);
+const Q128: U256 = uint!(0x100000000000000000000000000000000_U256);
+const MAX_U160: U256 = uint!(0xffffffffffffffffffffffffffffffffffffffff_U256);
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+// Ratios of 2^128 / sqrt(1.0001)^(2^i) used by `getSqrtRatioAtTick`
+const TICK_RATIOS: [(u32, U256); 19] = [
+    (0x2, uint!(0xfff97272373d413259a46990580e213a_U256)),
+    (0x4, uint!(0xfff2e50f5f656932ef12357cf3c7fdcc_U256)),
+    (0x8, uint!(0xffe5caca7e10e4e61c3624eaa0941cd0_U256)),
+    (0x10, uint!(0xffcb9843d60f6159c9db58835c926644_U256)),
+    (0x20, uint!(0xff973b41fa98c081472e6896dfb254c0_U256)),
+    (0x40, uint!(0xff2ea16466c96a3843ec78b326b52861_U256)),
+    (0x80, uint!(0xfe5dee046a99a2a811c461f1969c3053_U256)),
+    (0x100, uint!(0xfcbe86c7900a88aedcffc83b479aa3a4_U256)),
+    (0x200, uint!(0xf987a7253ac413176f2b074cf7815e54_U256)),
+    (0x400, uint!(0xf3392b0822b70005940c7a398e4b70f3_U256)),
+    (0x800, uint!(0xe7159475a2c29b7443b29c7fa6e889d9_U256)),
+    (0x1000, uint!(0xd097f3bdfd2022b8845ad8f792aa5825_U256)),
+    (0x2000, uint!(0xa9f746462d870fdf8a65dc1f90e061e5_U256)),
+    (0x4000, uint!(0x70d869a156d2a1b890bb3df62baf32f7_U256)),
+    (0x8000, uint!(0x31be135f97d08fd981231505542fcfa6_U256)),
+    (0x10000, uint!(0x9aa508b5b7a84e1c677de54f3e99bc9_U256)),
+    (0x20000, uint!(0x5d6af8dedb81196699c329225ee604_U256)),
+    (0x40000, uint!(0x2216e584f5fa1ea926041bedfe98_U256)),
+    (0x80000, uint!(0x48a170391f7dc42444e8fa2_U256)),
+];
+
+/// Net liquidity added when a tick is crossed from left to right.
+pub trait TickLiquidityNet {
+    fn liquidity_net(&self) -> i128;
+}
+
+impl TickLiquidityNet for i128 {
+    fn liquidity_net(&self) -> i128 {
+        *self
+    }
+}
+
+/// Result of a single swap step within one tick range.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// Port of `TickMath.getSqrtRatioAtTick`.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> eyre::Result<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return Err(eyre!("TICK_OUT_OF_RANGE {}", tick));
+    }
+
+    let mut ratio =
+        if abs_tick & 0x1 != 0 { uint!(0xfffcb933bd6fad37aa2d162d1a594001_U256) } else { uint!(0x100000000000000000000000000000000_U256) };
+    for (bit, tick_ratio) in TICK_RATIOS {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * tick_ratio) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // round up to get a sqrt price which is at least the price of the tick
+    let round_up = if ratio % U256::from(1u64 << 32) == U256::ZERO { U256::ZERO } else { U256::from(1) };
+    Ok((ratio >> 32) + round_up)
+}
+
+/// Greatest tick for which `get_sqrt_ratio_at_tick(tick) <= sqrt_price_x96`, like `TickMath.getTickAtSqrtRatio`.
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> eyre::Result<i32> {
+    if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
+        return Err(eyre!("SQRT_PRICE_OUT_OF_RANGE {}", sqrt_price_x96));
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if get_sqrt_ratio_at_tick(mid)? <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> eyre::Result<U256> {
+    let result = U512::from(a) * U512::from(b) / U512::from(denominator);
+    U256::uint_try_from(result).map_err(|_| eyre!("MUL_DIV_OVERFLOW"))
+}
+
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> eyre::Result<U256> {
+    let product = U512::from(a) * U512::from(b);
+    let denominator = U512::from(denominator);
+    let mut result = product / denominator;
+    if product % denominator != U512::ZERO {
+        result += U512::from(1);
+    }
+    U256::uint_try_from(result).map_err(|_| eyre!("MUL_DIV_OVERFLOW"))
+}
+
+fn div_rounding_up(a: U256, b: U256) -> U256 {
+    let result = a / b;
+    if a % b != U256::ZERO {
+        result + U256::from(1)
+    } else {
+        result
+    }
+}
+
+/// Port of `SqrtPriceMath.getAmount0Delta`.
+pub fn get_amount0_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> eyre::Result<U256> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if sqrt_ratio_a_x96.is_zero() {
+        return Err(eyre!("SQRT_PRICE_ZERO"));
+    }
+    let numerator1: U256 = U256::from(liquidity) << 96;
+    let numerator2 = sqrt_ratio_b_x96 - sqrt_ratio_a_x96;
+
+    if round_up {
+        Ok(div_rounding_up(mul_div_rounding_up(numerator1, numerator2, sqrt_ratio_b_x96)?, sqrt_ratio_a_x96))
+    } else {
+        Ok(mul_div(numerator1, numerator2, sqrt_ratio_b_x96)? / sqrt_ratio_a_x96)
+    }
+}
+
+/// Port of `SqrtPriceMath.getAmount1Delta`.
+pub fn get_amount1_delta(sqrt_ratio_a_x96: U256, sqrt_ratio_b_x96: U256, liquidity: u128, round_up: bool) -> eyre::Result<U256> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), sqrt_ratio_b_x96 - sqrt_ratio_a_x96, Q96)
+    } else {
+        mul_div(U256::from(liquidity), sqrt_ratio_b_x96 - sqrt_ratio_a_x96, Q96)
+    }
+}
+
+fn sort(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96: U256, liquidity: u128, amount: U256) -> eyre::Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price_x96);
+    }
+    let numerator1: U256 = U256::from(liquidity) << 96;
+    if let Some(product) = amount.checked_mul(sqrt_price_x96) {
+        if let Some(denominator) = numerator1.checked_add(product) {
+            return mul_div_rounding_up(numerator1, sqrt_price_x96, denominator);
+        }
+    }
+    Ok(div_rounding_up(numerator1, (numerator1 / sqrt_price_x96) + amount))
+}
+
+fn get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96: U256, liquidity: u128, amount: U256) -> eyre::Result<U256> {
+    let quotient = if amount <= MAX_U160 { (amount << 96) / U256::from(liquidity) } else { mul_div(amount, Q96, U256::from(liquidity))? };
+    sqrt_price_x96.checked_add(quotient).filter(|sqrt_price| *sqrt_price <= MAX_U160).ok_or_else(|| eyre!("SQRT_PRICE_OVERFLOW"))
+}
+
+/// Port of `SqrtPriceMath.getNextSqrtPriceFromInput`.
+pub fn get_next_sqrt_price_from_input(sqrt_price_x96: U256, liquidity: u128, amount_in: U256, zero_for_one: bool) -> eyre::Result<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        return Err(eyre!("INVALID_PRICE_OR_LIQUIDITY"));
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_in)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_in)
+    }
+}
+
+/// Port of `SwapMath.computeSwapStep` for an exact input amount.
+pub fn compute_swap_step(
+    sqrt_ratio_current_x96: U256,
+    sqrt_ratio_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_pips: u32,
+) -> eyre::Result<SwapStep> {
+    let zero_for_one = sqrt_ratio_current_x96 >= sqrt_ratio_target_x96;
+
+    let amount_remaining_less_fee = mul_div(amount_remaining, U256::from(FEE_DENOMINATOR - fee_pips), U256::from(FEE_DENOMINATOR))?;
+    let amount_in_to_target = if zero_for_one {
+        get_amount0_delta(sqrt_ratio_target_x96, sqrt_ratio_current_x96, liquidity, true)?
+    } else {
+        get_amount1_delta(sqrt_ratio_current_x96, sqrt_ratio_target_x96, liquidity, true)?
+    };
+    let sqrt_price_next_x96 = if amount_remaining_less_fee >= amount_in_to_target {
+        sqrt_ratio_target_x96
+    } else {
+        get_next_sqrt_price_from_input(sqrt_ratio_current_x96, liquidity, amount_remaining_less_fee, zero_for_one)?
+    };
+    let max = sqrt_ratio_target_x96 == sqrt_price_next_x96;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_in =
+            if max { amount_in_to_target } else { get_amount0_delta(sqrt_price_next_x96, sqrt_ratio_current_x96, liquidity, true)? };
+        (amount_in, get_amount1_delta(sqrt_price_next_x96, sqrt_ratio_current_x96, liquidity, false)?)
+    } else {
+        let amount_in =
+            if max { amount_in_to_target } else { get_amount1_delta(sqrt_ratio_current_x96, sqrt_price_next_x96, liquidity, true)? };
+        (amount_in, get_amount0_delta(sqrt_ratio_current_x96, sqrt_price_next_x96, liquidity, false)?)
+    };
+
+    let fee_amount = if !max {
+        // the remainder of the input is taken as fee
+        amount_remaining - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, U256::from(fee_pips), U256::from(FEE_DENOMINATOR - fee_pips))?
+    };
+
+    Ok(SwapStep { sqrt_price_next_x96, amount_in, amount_out, fee_amount })
+}
+
+/// Port of `TickBitmap.nextInitializedTickWithinOneWord` using a map of the initialized ticks instead of the bitmap words.
+pub fn next_initialized_tick_within_one_word<V>(ticks: &BTreeMap<i32, V>, tick: i32, tick_spacing: i32, lte: bool) -> (i32, bool) {
+    let compressed = tick.div_euclid(tick_spacing);
+    if lte {
+        let bit_pos = compressed.rem_euclid(256);
+        let word_start = (compressed - bit_pos) * tick_spacing;
+        match ticks.range(word_start..=compressed * tick_spacing).next_back() {
+            Some((next, _)) => (*next, true),
+            None => (word_start, false),
+        }
+    } else {
+        let bit_pos = (compressed + 1).rem_euclid(256);
+        let word_end = (compressed + 1 + (255 - bit_pos)) * tick_spacing;
+        match ticks.range((compressed + 1) * tick_spacing..=word_end).next() {
+            Some((next, _)) => (*next, true),
+            None => (word_end, false),
+        }
+    }
+}
+
+/// Simulate an exact input swap like `UniswapV3Pool.swap` and return the output amount. `ticks` holds every initialized
+/// tick of the pool.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_exact_input<V: TickLiquidityNet>(
+    sqrt_price_x96: U256,
+    tick: i32,
+    liquidity: u128,
+    ticks: &BTreeMap<i32, V>,
+    tick_spacing: i32,
+    fee_pips: u32,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> eyre::Result<U256> {
+    let sqrt_price_limit_x96 = if zero_for_one { MIN_SQRT_RATIO + U256::from(1) } else { MAX_SQRT_RATIO - U256::from(1) };
+
+    let mut amount_remaining = amount_in;
+    let mut amount_out = U256::ZERO;
+    let mut sqrt_price_x96 = sqrt_price_x96;
+    let mut tick = tick;
+    let mut liquidity = liquidity;
+
+    while !amount_remaining.is_zero() && sqrt_price_x96 != sqrt_price_limit_x96 {
+        let sqrt_price_start_x96 = sqrt_price_x96;
+        let (tick_next, initialized) = next_initialized_tick_within_one_word(ticks, tick, tick_spacing, zero_for_one);
+        let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_x96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+        let sqrt_price_target_x96 = if (zero_for_one && sqrt_price_next_x96 < sqrt_price_limit_x96)
+            || (!zero_for_one && sqrt_price_next_x96 > sqrt_price_limit_x96)
+        {
+            sqrt_price_limit_x96
+        } else {
+            sqrt_price_next_x96
+        };
+
+        let step = compute_swap_step(sqrt_price_x96, sqrt_price_target_x96, liquidity, amount_remaining, fee_pips)?;
+        sqrt_price_x96 = step.sqrt_price_next_x96;
+        amount_remaining -= step.amount_in + step.fee_amount;
+        amount_out += step.amount_out;
+
+        if sqrt_price_x96 == sqrt_price_next_x96 {
+            if initialized {
+                let net = ticks.get(&tick_next).map(|tick_info| tick_info.liquidity_net()).unwrap_or_default();
+                let net = if zero_for_one { -net } else { net };
+                liquidity = liquidity.checked_add_signed(net).ok_or_else(|| eyre!("LIQUIDITY_OVERFLOW"))?;
+            }
+            tick = if zero_for_one { tick_next - 1 } else { tick_next };
+        } else if sqrt_price_x96 != sqrt_price_start_x96 {
+            tick = get_tick_at_sqrt_ratio(sqrt_price_x96)?;
+        }
+    }
+
+    Ok(amount_out)
+}
+
+/// Port of `Tick.tickSpacingToMaxLiquidityPerTick`.
+pub fn tick_spacing_to_max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+/// True if the tick is in the tick range and a multiple of the tick spacing.
+pub fn is_valid_tick(tick: i32, tick_spacing: i32) -> bool {
+    (MIN_TICK..=MAX_TICK).contains(&tick) && tick % tick_spacing == 0
+}
+
+/// Token amounts of the liquidity at the current price like `LiquidityAmounts.getAmountsForLiquidity`, rounded down.
+pub fn get_amounts_for_liquidity(sqrt_price_x96: U256, tick_lower: i32, tick_upper: i32, liquidity: u128) -> eyre::Result<(U256, U256)> {
+    let sqrt_ratio_a_x96 = get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_ratio_b_x96 = get_sqrt_ratio_at_tick(tick_upper)?;
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if sqrt_price_x96 <= sqrt_ratio_a_x96 {
+        Ok((get_amount0_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity, false)?, U256::ZERO))
+    } else if sqrt_price_x96 < sqrt_ratio_b_x96 {
+        Ok((
+            get_amount0_delta(sqrt_price_x96, sqrt_ratio_b_x96, liquidity, false)?,
+            get_amount1_delta(sqrt_ratio_a_x96, sqrt_price_x96, liquidity, false)?,
+        ))
+    } else {
+        Ok((U256::ZERO, get_amount1_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity, false)?))
+    }
+}
+
+/// Port of `Tick.getFeeGrowthInside` for one token. The fee growth values overflow by design.
+pub fn get_fee_growth_inside(
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_x128: U256,
+    lower_fee_growth_outside_x128: U256,
+    upper_fee_growth_outside_x128: U256,
+) -> U256 {
+    let fee_growth_below = if tick_current >= tick_lower {
+        lower_fee_growth_outside_x128
+    } else {
+        fee_growth_global_x128.wrapping_sub(lower_fee_growth_outside_x128)
+    };
+    let fee_growth_above = if tick_current < tick_upper {
+        upper_fee_growth_outside_x128
+    } else {
+        fee_growth_global_x128.wrapping_sub(upper_fee_growth_outside_x128)
+    };
+    fee_growth_global_x128.wrapping_sub(fee_growth_below).wrapping_sub(fee_growth_above)
+}
+
+/// Fees earned by the liquidity since the last checkpoint like `Position.update`, truncated to uint128 as in the pool.
+pub fn get_fees_owed(liquidity: u128, fee_growth_inside_x128: U256, fee_growth_inside_last_x128: U256) -> U256 {
+    let fee_growth_delta = fee_growth_inside_x128.wrapping_sub(fee_growth_inside_last_x128);
+    let fees = U512::from(fee_growth_delta) * U512::from(liquidity) / U512::from(Q128);
+    U256::from(fees.wrapping_to::<u128>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_sqrt_ratio_at_tick() -> eyre::Result<()> {
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK)?, MIN_SQRT_RATIO);
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK)?, MAX_SQRT_RATIO);
+        assert_eq!(get_sqrt_ratio_at_tick(0)?, Q96);
+        assert_eq!(get_sqrt_ratio_at_tick(1)?, U256::from(79232123823359799118286999568u128));
+        assert_eq!(get_sqrt_ratio_at_tick(-1)?, U256::from(79224201403219477170569942574u128));
+        assert!(get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tick_at_sqrt_ratio() -> eyre::Result<()> {
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO)?, MIN_TICK);
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256::from(1))?, MAX_TICK - 1);
+        assert_eq!(get_tick_at_sqrt_ratio(U256::from(1614245643731953243882325864332250u128))?, 198450);
+        for tick in [-500000, -60, -1, 0, 1, 60, 500000] {
+            assert_eq!(get_tick_at_sqrt_ratio(get_sqrt_ratio_at_tick(tick)?)?, tick);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_swap_step() -> eyre::Result<()> {
+        // values from the SwapMath tests of v3-core
+        let step = compute_swap_step(
+            uint!(79228162514264337593543950336_U256),
+            uint!(79623317895830914510639640423_U256),
+            2000000000000000000,
+            U256::from(1000000000000000000u128),
+            600,
+        )?;
+        assert_eq!(step.amount_in, U256::from(9975124224178055u128));
+        assert_eq!(step.fee_amount, U256::from(5988667735148u128));
+        assert_eq!(step.amount_out, U256::from(9925619580021728u128));
+        assert_eq!(step.sqrt_price_next_x96, uint!(79623317895830914510639640423_U256));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick_spacing_to_max_liquidity_per_tick() {
+        assert_eq!(tick_spacing_to_max_liquidity_per_tick(10), 1917569901783203986719870431555990);
+        assert_eq!(tick_spacing_to_max_liquidity_per_tick(60), 11505743598341114571880798222544994);
+        assert_eq!(tick_spacing_to_max_liquidity_per_tick(200), 38350317471085141830651933667504588);
+        assert!(is_valid_tick(-887220, 60));
+        assert!(!is_valid_tick(-887272, 60));
+        assert!(!is_valid_tick(887273, 1));
+    }
+
+    #[test]
+    fn test_get_amounts_for_liquidity() -> eyre::Result<()> {
+        let liquidity = 10u128.pow(18);
+        // in range at tick 0
+        let amounts = get_amounts_for_liquidity(Q96, -60, 60, liquidity)?;
+        assert_eq!(amounts, (U256::from(2995354955910780u128), U256::from(2995354955910780u128)));
+        // below and above the range
+        assert_eq!(
+            get_amounts_for_liquidity(get_sqrt_ratio_at_tick(-120)?, -60, 60, liquidity)?,
+            (U256::from(5999709018652706u128), U256::ZERO)
+        );
+        assert_eq!(
+            get_amounts_for_liquidity(get_sqrt_ratio_at_tick(120)?, -60, 60, liquidity)?,
+            (U256::ZERO, U256::from(5999709018652706u128))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_fee_growth_inside() {
+        let (global, lower, upper) = (U256::from(1000), U256::from(100), U256::from(200));
+        // in range: global - below - above
+        assert_eq!(get_fee_growth_inside(-60, 60, 0, global, lower, upper), U256::from(700));
+        // below the range, fee growth below is global - lower
+        assert_eq!(get_fee_growth_inside(-60, 60, -120, global, lower, upper), U256::from(100).wrapping_sub(U256::from(200)));
+        // above the range, fee growth above is global - upper
+        assert_eq!(get_fee_growth_inside(-60, 60, 120, global, lower, upper), U256::from(100));
+    }
+
+    #[test]
+    fn test_get_fees_owed() {
+        let liquidity = 10u128.pow(18);
+        assert_eq!(get_fees_owed(liquidity, Q128 * U256::from(3), Q128), U256::from(2 * liquidity));
+        // fee growth overflowed since the last checkpoint
+        assert_eq!(get_fees_owed(liquidity, Q128, U256::MAX - Q128 + U256::from(1)), U256::from(2 * liquidity));
+        assert_eq!(get_fees_owed(liquidity, Q128, Q128), U256::ZERO);
+    }
+
+    #[test]
+    fn test_next_initialized_tick_within_one_word() {
+        let ticks: BTreeMap<i32, ()> =
+            [(-200, ()), (-55, ()), (-4, ()), (70, ()), (78, ()), (84, ()), (139, ()), (240, ()), (535, ())].into();
+
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, 78, 1, false), (84, true));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, -55, 1, false), (-4, true));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, 255, 1, false), (511, false));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, 78, 1, true), (78, true));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, 79, 1, true), (78, true));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, 258, 1, true), (256, false));
+        assert_eq!(next_initialized_tick_within_one_word(&ticks, -257, 1, true), (-512, false));
+    }
+
+    #[test]
+    fn test_swap_exact_input_single_range() -> eyre::Result<()> {
+        // Without crossing a tick a swap equals a single swap step
+        let liquidity = 2000000000000000000u128;
+        let liquidity_net = BTreeMap::from([(-600, liquidity as i128), (600, -(liquidity as i128))]);
+        let amount_in = U256::from(1000000000000000u128);
+
+        let amount_out = swap_exact_input(Q96, 0, liquidity, &liquidity_net, 60, 3000, amount_in, true)?;
+        let step = compute_swap_step(Q96, get_sqrt_ratio_at_tick(-600)?, liquidity, amount_in, 3000)?;
+        assert_eq!(amount_out, step.amount_out);
+        assert!(amount_out < amount_in);
+        Ok(())
+    }
+}
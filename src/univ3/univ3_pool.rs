@@ -1,11 +1,61 @@
-use crate::univ3::ticks::TICKS_SLOT;
+use crate::storage_struct;
+use crate::univ3::ticks::{tick_index, TickIndex, TICKS_SLOT};
+use crate::univ3::univ3_math::{
+    get_amounts_for_liquidity, get_fee_growth_inside, get_fees_owed, swap_exact_input, TickLiquidityNet, MAX_TICK, MIN_TICK,
+};
+use crate::utils::{
+    missing_slot, read_activity, read_all_storage_entries, read_required_storage, read_storage, read_storage_history, read_storage_proof,
+    slot_offset, DexSyncError, Field, Mapping, PoolActivity,
+};
+use alloy::rpc::types::EIP1186AccountProofResponse;
 use alloy_primitives::aliases::{I24, I56, U24};
-use alloy_primitives::{b256, keccak256, Address, B256, I128, U128, U16, U160, U256};
+use alloy_primitives::{b256, keccak256, Address, BlockNumber, StorageValue, B256, I128, U128, U16, U160, U256};
 use alloy_sol_types::SolValue;
-use eyre::eyre;
+use reth_db::Database;
+use reth_primitives::StorageEntry;
 use reth_provider::StateProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
 
-const LIQUIDITY_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000004");
+pub(crate) const FEE_GROWTH_GLOBAL0_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000001");
+pub(crate) const FEE_GROWTH_GLOBAL1_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000002");
+pub(crate) const PROTOCOL_FEES_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000003");
+pub(crate) const LIQUIDITY_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000004");
+pub(crate) const TICK_BITMAP_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000006");
+const POSITIONS_SLOT: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000007");
+
+storage_struct! {
+    /// `Tick.Info`
+    pub(crate) struct TickInfoSlots[4] {
+        liquidity_gross: U128 => (0, 0),
+        liquidity_net: I128 => (0, 16),
+        fee_growth_outside0_x128: U256 => (1, 0),
+        fee_growth_outside1_x128: U256 => (2, 0),
+        tick_cumulative_outside: I56 => (3, 0),
+        seconds_per_liquidity_outside_x128: U160 => (3, 7),
+        seconds_outside: u32 => (3, 27),
+        initialized: bool => (3, 31),
+    }
+}
+
+storage_struct! {
+    /// `Position.Info`
+    struct PositionInfoSlots[4] {
+        liquidity: U128 => (0, 0),
+        fee_growth_inside0_last_x128: U256 => (1, 0),
+        fee_growth_inside1_last_x128: U256 => (2, 0),
+        tokens_owed0: U128 => (3, 0),
+        tokens_owed1: U128 => (3, 16),
+    }
+}
+
+/// `mapping(int24 => Tick.Info) ticks`
+pub(crate) const TICKS: Mapping<I24, TickInfoSlots> = Mapping::new(TICKS_SLOT);
+/// `mapping(int16 => uint256) tickBitmap`
+pub(crate) const TICK_BITMAP: Mapping<i16, Field<U256>> = Mapping::new(TICK_BITMAP_SLOT);
+/// `mapping(bytes32 => Position.Info) positions` keyed by `keccak256(abi.encodePacked(owner, tickLower, tickUpper))`
+const POSITIONS: Mapping<B256, PositionInfoSlots> = Mapping::new(POSITIONS_SLOT);
 
 #[derive(Debug)]
 pub struct Univ3Pool {
@@ -15,7 +65,14 @@ pub struct Univ3Pool {
     pub fee: U24,
 }
 
-#[derive(Debug)]
+impl Univ3Pool {
+    /// CREATE2 salt of the pool like `PoolAddress.computeAddress`.
+    pub fn salt(&self) -> B256 {
+        keccak256((self.token0, self.token1, self.fee).abi_encode())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Univ3Slot0 {
     pub sqrt_price_x96: U160,
     pub tick: I24,
@@ -26,7 +83,7 @@ pub struct Univ3Slot0 {
     pub unlocked: bool,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TickInfo {
     pub liquidity_gross: U128,
     pub liquidity_net: I128,
@@ -38,96 +95,433 @@ pub struct TickInfo {
     pub initialized: bool,
 }
 
-pub fn read_liquidity<T: StateProvider>(provider: T, pool_address: Address) -> eyre::Result<U128> {
-    match provider.storage(pool_address, LIQUIDITY_SLOT) {
-        Ok(storage_value) => match storage_value {
-            None => Ok(U128::ZERO), // the pool has no liquidity
-            Some(value) => {
-                let bytes: [u8; 32] = value.to_be_bytes();
-                Ok(U128::from_be_slice(&bytes[16..32]))
-            }
-        },
-        Err(e) => Err(eyre!(e)),
+/// Pool wide fee accounting: fee growth per unit of liquidity and the uncollected protocol fees.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Univ3PoolGlobals {
+    pub fee_growth_global0_x128: U256,
+    pub fee_growth_global1_x128: U256,
+    pub protocol_fees0: U128,
+    pub protocol_fees1: U128,
+}
+
+/// Liquidity and fee checkpoints of a position in a pool or in the position manager.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionInfo {
+    pub liquidity: U128,
+    pub fee_growth_inside0_last_x128: U256,
+    pub fee_growth_inside1_last_x128: U256,
+    pub tokens_owed0: U128,
+    pub tokens_owed1: U128,
+}
+
+/// Underlying token amounts and uncollected fees of a position at the current pool price.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionValue {
+    pub amount0: U256,
+    pub amount1: U256,
+    pub fees0: U256,
+    pub fees1: U256,
+}
+
+/// A position minted directly on the pool, identified by owner and tick range.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Univ3PoolPosition {
+    pub pool: Address,
+    pub owner: Address,
+    pub tick_lower: I24,
+    pub tick_upper: I24,
+    pub info: PositionInfo,
+}
+
+impl Univ3PoolPosition {
+    /// Underlying token amounts and uncollected fees at the state of the provider.
+    pub fn value<T: StateProvider>(&self, provider: T) -> eyre::Result<PositionValue> {
+        read_position_value(provider, self.pool, self.tick_lower, self.tick_upper, &self.info)
     }
 }
 
+/// State of a pool required to quote swaps.
+#[derive(Debug)]
+pub struct Univ3PoolState {
+    pub address: Address,
+    pub fee: U24,
+    pub tick_spacing: i32,
+    pub slot0: Univ3Slot0,
+    pub liquidity: U128,
+    pub ticks: BTreeMap<i32, TickInfo>,
+}
+
+impl Univ3PoolState {
+    /// Output amount for an exact input swap using the same math as the pool contract.
+    pub fn quote_exact_input(&self, amount_in: U256, zero_for_one: bool) -> eyre::Result<U256> {
+        swap_exact_input(
+            U256::from(self.slot0.sqrt_price_x96),
+            self.slot0.tick.as_i32(),
+            self.liquidity.to::<u128>(),
+            &self.ticks,
+            self.tick_spacing,
+            self.fee.to::<u32>(),
+            amount_in,
+            zero_for_one,
+        )
+    }
+}
+
+impl TickLiquidityNet for TickInfo {
+    fn liquidity_net(&self) -> i128 {
+        i128::from_be_bytes(self.liquidity_net.to_be_bytes::<16>())
+    }
+}
+
+/// Slot0 and liquidity of a pool after a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Univ3PoolChange {
+    pub block_number: BlockNumber,
+    pub slot0: Option<Univ3Slot0>,
+    pub liquidity: U128,
+}
+
+/// All slot0 and liquidity changes of a pool in a block range.
+#[derive(Debug)]
+pub struct Univ3PoolHistory {
+    pub address: Address,
+    pub initial_slot0: Option<Univ3Slot0>,
+    pub initial_liquidity: U128,
+    pub changes: Vec<Univ3PoolChange>,
+}
+
+pub fn read_liquidity<T: StateProvider>(provider: T, pool_address: Address) -> eyre::Result<U128> {
+    // zero if the pool has no liquidity
+    Ok(decode_liquidity(read_storage(&provider, pool_address, LIQUIDITY_SLOT)?.unwrap_or_default()))
+}
+
+/// Decode the liquidity slot of a pool.
+pub fn decode_liquidity(value: StorageValue) -> U128 {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    U128::from_be_slice(&bytes[16..32])
+}
+
+/// Proof of the slot0 and liquidity slots of the pool, see `read_storage_proof`.
+pub fn read_slot0_proof<T: StateProvider>(provider: T, pool_address: Address) -> Result<EIP1186AccountProofResponse, DexSyncError> {
+    read_storage_proof(provider, pool_address, &[B256::ZERO, LIQUIDITY_SLOT])
+}
+
 pub fn read_slot0<T: StateProvider>(provider: T, pool_address: Address) -> eyre::Result<Option<Univ3Slot0>> {
-    match provider.storage(pool_address, B256::ZERO) {
-        Ok(storage_value) => match storage_value {
-            None => Ok(None), // pool not found
-            Some(value) => {
-                let bytes: [u8; 32] = value.to_be_bytes();
-                let unlocked = bytes[1] != 0;
-                let fee_protocol = bytes[2];
-                let observation_cardinality_next = U16::from_be_slice(&bytes[3..5]);
-                let observation_cardinality = U16::from_be_slice(&bytes[5..7]);
-                let observation_index = U16::from_be_slice(&bytes[7..9]);
-                let tick = I24::try_from_be_slice(&bytes[9..12]).unwrap();
-                let sqrt_price_x96 = U160::from_be_slice(&bytes[12..32]);
-                Ok(Some(Univ3Slot0 {
-                    unlocked,
-                    fee_protocol,
-                    observation_cardinality_next,
-                    observation_cardinality,
-                    observation_index,
-                    tick,
-                    sqrt_price_x96,
-                }))
-            }
-        },
-        Err(e) => Err(eyre!(e)),
+    // none if pool not found
+    Ok(read_storage(&provider, pool_address, B256::ZERO)?.map(decode_slot0))
+}
+
+/// Decode the packed slot0 of a pool.
+pub fn decode_slot0(value: StorageValue) -> Univ3Slot0 {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    let unlocked = bytes[1] != 0;
+    let fee_protocol = bytes[2];
+    let observation_cardinality_next = U16::from_be_slice(&bytes[3..5]);
+    let observation_cardinality = U16::from_be_slice(&bytes[5..7]);
+    let observation_index = U16::from_be_slice(&bytes[7..9]);
+    let tick = I24::try_from_be_slice(&bytes[9..12]).unwrap();
+    let sqrt_price_x96 = U160::from_be_slice(&bytes[12..32]);
+    Univ3Slot0 { unlocked, fee_protocol, observation_cardinality_next, observation_cardinality, observation_index, tick, sqrt_price_x96 }
+}
+
+/// Creation block and last slot0 change of the pool from the history indices. Every swap writes slot0.
+pub fn read_pool_activity<DB: Database>(db: &DB, pool_address: Address) -> eyre::Result<PoolActivity> {
+    read_activity(db, pool_address, B256::ZERO)
+}
+
+/// Read all slot0 and liquidity changes of the pools in the block range by walking the storage history index.
+pub fn read_pools_history<DB: Database>(
+    db: &DB,
+    pool_addresses: &[Address],
+    block_range: RangeInclusive<BlockNumber>,
+) -> eyre::Result<Vec<Univ3PoolHistory>> {
+    let mut result = Vec::with_capacity(pool_addresses.len());
+    for pool_address in pool_addresses {
+        let slot0_history = read_storage_history(db, *pool_address, B256::ZERO, block_range.clone())?;
+        let liquidity_history = read_storage_history(db, *pool_address, LIQUIDITY_SLOT, block_range.clone())?;
+
+        // Merge both slots by block number. A slot without change in a block keeps its previous value.
+        let mut changed: BTreeMap<BlockNumber, (Option<StorageValue>, Option<StorageValue>)> = BTreeMap::new();
+        for change in slot0_history.changes {
+            changed.entry(change.block_number).or_default().0 = Some(change.value);
+        }
+        for change in liquidity_history.changes {
+            changed.entry(change.block_number).or_default().1 = Some(change.value);
+        }
+
+        let mut slot0_value = slot0_history.initial_value;
+        let mut liquidity_value = liquidity_history.initial_value;
+        let mut changes = Vec::with_capacity(changed.len());
+        for (block_number, (slot0_change, liquidity_change)) in changed {
+            slot0_value = slot0_change.unwrap_or(slot0_value);
+            liquidity_value = liquidity_change.unwrap_or(liquidity_value);
+            changes.push(Univ3PoolChange {
+                block_number,
+                slot0: decode_optional_slot0(slot0_value),
+                liquidity: decode_liquidity(liquidity_value),
+            });
+        }
+
+        result.push(Univ3PoolHistory {
+            address: *pool_address,
+            initial_slot0: decode_optional_slot0(slot0_history.initial_value),
+            initial_liquidity: decode_liquidity(liquidity_history.initial_value),
+            changes,
+        });
     }
+    Ok(result)
 }
 
-pub fn read_tick<T: StateProvider>(provider: T, pool_address: Address, tick: I24) -> eyre::Result<Option<TickInfo>> {
-    let storage_key0 = keccak256((tick, TICKS_SLOT).abi_encode());
-    let storage_key1 = B256::from(U256::from_be_slice(storage_key0.0.as_slice()) + U256::from(1));
-    let storage_key2 = B256::from(U256::from_be_slice(storage_key0.0.as_slice()) + U256::from(2));
-    let storage_key3 = B256::from(U256::from_be_slice(storage_key0.0.as_slice()) + U256::from(3));
+// An empty slot0 means the pool is not initialized.
+fn decode_optional_slot0(value: StorageValue) -> Option<Univ3Slot0> {
+    if value.is_zero() {
+        None
+    } else {
+        Some(decode_slot0(value))
+    }
+}
 
-    let Some(storage_value0) = provider.storage(pool_address, storage_key0)? else {
-        return Ok(None);
+/// Read `feeGrowthGlobal0X128` and `feeGrowthGlobal1X128`. Zero if no fees were collected yet.
+pub fn read_fee_growth_global<T: StateProvider>(provider: T, pool_address: Address) -> eyre::Result<(U256, U256)> {
+    let fee_growth_global0_x128 = read_storage(&provider, pool_address, FEE_GROWTH_GLOBAL0_SLOT)?.unwrap_or_default();
+    let fee_growth_global1_x128 = read_storage(&provider, pool_address, FEE_GROWTH_GLOBAL1_SLOT)?.unwrap_or_default();
+    Ok((fee_growth_global0_x128, fee_growth_global1_x128))
+}
+
+/// Read fee growth globals and the protocol fees of a pool.
+pub fn read_pool_globals<T: StateProvider>(provider: T, pool_address: Address) -> eyre::Result<Univ3PoolGlobals> {
+    let (fee_growth_global0_x128, fee_growth_global1_x128) = read_fee_growth_global(&provider, pool_address)?;
+    let (protocol_fees0, protocol_fees1) =
+        decode_protocol_fees(read_storage(&provider, pool_address, PROTOCOL_FEES_SLOT)?.unwrap_or_default());
+    Ok(Univ3PoolGlobals { fee_growth_global0_x128, fee_growth_global1_x128, protocol_fees0, protocol_fees1 })
+}
+
+/// Decode the packed `ProtocolFees { uint128 token0; uint128 token1; }` slot.
+pub fn decode_protocol_fees(value: StorageValue) -> (U128, U128) {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    (U128::from_be_slice(&bytes[16..32]), U128::from_be_slice(&bytes[0..16]))
+}
+
+/// Fee growth per unit of liquidity inside the tick range like `Tick.getFeeGrowthInside`. Fees earned by a range between two
+/// blocks are the difference of the fee growth inside multiplied by the liquidity.
+pub fn read_fee_growth_inside<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    tick_lower: I24,
+    tick_upper: I24,
+) -> eyre::Result<(U256, U256)> {
+    let Some(slot0) = read_slot0(&provider, pool_address)? else {
+        return Err(missing_slot(&provider, pool_address, B256::ZERO).into());
     };
-    let Some(storage_value1) = provider.storage(pool_address, storage_key1)? else {
-        return Err(eyre!("STORAGE_SLOT_1_NOT_FOUND"));
+    fee_growth_inside(&provider, pool_address, slot0.tick, tick_lower, tick_upper)
+}
+
+fn fee_growth_inside<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    tick_current: I24,
+    tick_lower: I24,
+    tick_upper: I24,
+) -> eyre::Result<(U256, U256)> {
+    let (fee_growth_global0_x128, fee_growth_global1_x128) = read_fee_growth_global(&provider, pool_address)?;
+    // ticks of a position without liquidity may be cleared, the fee growth outside is zero then
+    let fee_growth_outside = |tick: I24| -> eyre::Result<(U256, U256)> {
+        Ok(read_tick(&provider, pool_address, tick)?
+            .map(|tick_info| (tick_info.fee_growth_outside_0x128, tick_info.fee_growth_outside_1x128))
+            .unwrap_or_default())
     };
-    let Some(storage_value2) = provider.storage(pool_address, storage_key2)? else {
-        return Err(eyre!("STORAGE_SLOT_2_NOT_FOUND"));
+    let (lower0, lower1) = fee_growth_outside(tick_lower)?;
+    let (upper0, upper1) = fee_growth_outside(tick_upper)?;
+
+    let (tick_lower, tick_upper, tick_current) = (tick_lower.as_i32(), tick_upper.as_i32(), tick_current.as_i32());
+    Ok((
+        get_fee_growth_inside(tick_lower, tick_upper, tick_current, fee_growth_global0_x128, lower0, upper0),
+        get_fee_growth_inside(tick_lower, tick_upper, tick_current, fee_growth_global1_x128, lower1, upper1),
+    ))
+}
+
+/// Read a position of the pool's `positions` mapping. A position that does not exist is all zero.
+pub fn read_pool_position<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    owner: Address,
+    tick_lower: I24,
+    tick_upper: I24,
+) -> eyre::Result<Univ3PoolPosition> {
+    let position = POSITIONS.entry(&keccak256((owner, tick_lower, tick_upper).abi_encode_packed()));
+
+    let liquidity = position.liquidity().read(&provider, pool_address)?;
+    let fee_growth_inside0_last_x128 = position.fee_growth_inside0_last_x128().read(&provider, pool_address)?;
+    let fee_growth_inside1_last_x128 = position.fee_growth_inside1_last_x128().read(&provider, pool_address)?;
+    let value = read_storage(&provider, pool_address, position.tokens_owed0().slot)?.unwrap_or_default();
+    let tokens_owed0 = position.tokens_owed0().decode(value);
+    let tokens_owed1 = position.tokens_owed1().decode(value);
+
+    Ok(Univ3PoolPosition {
+        pool: pool_address,
+        owner,
+        tick_lower,
+        tick_upper,
+        info: PositionInfo { liquidity, fee_growth_inside0_last_x128, fee_growth_inside1_last_x128, tokens_owed0, tokens_owed1 },
+    })
+}
+
+/// Read the positions of the pool for a list of owners and tick ranges.
+pub fn read_pool_positions<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    positions: &[(Address, I24, I24)],
+) -> eyre::Result<Vec<Univ3PoolPosition>> {
+    positions
+        .iter()
+        .map(|(owner, tick_lower, tick_upper)| read_pool_position(&provider, pool_address, *owner, *tick_lower, *tick_upper))
+        .collect()
+}
+
+/// Compute the token amounts and uncollected fees of a position from slot0, the fee growth and the ticks of the range.
+pub fn read_position_value<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    tick_lower: I24,
+    tick_upper: I24,
+    position: &PositionInfo,
+) -> eyre::Result<PositionValue> {
+    let Some(slot0) = read_slot0(&provider, pool_address)? else {
+        return Err(missing_slot(&provider, pool_address, B256::ZERO).into());
     };
-    let Some(storage_value3) = provider.storage(pool_address, storage_key3)? else {
-        return Err(eyre!("STORAGE_SLOT_3_NOT_FOUND"));
+    let (fee_growth_inside0_x128, fee_growth_inside1_x128) =
+        fee_growth_inside(&provider, pool_address, slot0.tick, tick_lower, tick_upper)?;
+
+    let liquidity = position.liquidity.to::<u128>();
+    let (amount0, amount1) =
+        get_amounts_for_liquidity(U256::from(slot0.sqrt_price_x96), tick_lower.as_i32(), tick_upper.as_i32(), liquidity)?;
+    let fees0 =
+        U256::from(position.tokens_owed0) + get_fees_owed(liquidity, fee_growth_inside0_x128, position.fee_growth_inside0_last_x128);
+    let fees1 =
+        U256::from(position.tokens_owed1) + get_fees_owed(liquidity, fee_growth_inside1_x128, position.fee_growth_inside1_last_x128);
+    Ok(PositionValue { amount0, amount1, fees0, fees1 })
+}
+
+/// Tick spacing of the fee tiers enabled in the factory by default.
+pub fn tick_spacing_from_fee(fee: U24) -> Option<i32> {
+    match fee.to::<u32>() {
+        100 => Some(1),
+        500 => Some(10),
+        3000 => Some(60),
+        10000 => Some(200),
+        _ => None,
+    }
+}
+
+/// Read slot0, liquidity and all initialized ticks of a pool. Returns `None` if the pool is not initialized.
+pub fn read_pool_state<T: StateProvider>(
+    provider: T,
+    pool_address: Address,
+    fee: U24,
+    tick_spacing: i32,
+) -> eyre::Result<Option<Univ3PoolState>> {
+    let Some(slot0) = read_slot0(&provider, pool_address)? else {
+        return Ok(None);
     };
-    // slot0
-    let bytes: [u8; 32] = storage_value0.to_be_bytes();
-    let liquidity_net = I128::try_from_be_slice(&bytes[0..16]).unwrap();
-    let liquidity_gross = U128::from_be_slice(&bytes[16..32]);
-    // slot1
-    let fee_growth_outside_0x128 = U256::from(storage_value1);
-    // slot2
-    let fee_growth_outside_1x128 = U256::from(storage_value2);
-    // slot3
-    let bytes: [u8; 32] = storage_value3.to_be_bytes();
-    let initialized: bool = bytes[0] != 0;
-    let seconds_outside: u32 = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
-    let seconds_per_liquidity_outside_x128 = U160::from_be_slice(&bytes[5..25]);
-    let tick_cumulative_outside = I56::try_from_be_slice(&bytes[25..32]).unwrap();
-
-    Ok(Some(TickInfo {
-        liquidity_gross,
-        liquidity_net,
-        fee_growth_outside_0x128,
-        fee_growth_outside_1x128,
-        tick_cumulative_outside,
-        seconds_per_liquidity_outside_x128,
-        seconds_outside,
-        initialized,
-    }))
+    let liquidity = read_liquidity(&provider, pool_address)?;
+    let ticks = read_ticks(&provider, pool_address, tick_spacing)?;
+    Ok(Some(Univ3PoolState { address: pool_address, fee, tick_spacing, slot0, liquidity, ticks }))
+}
+
+/// Read a word of the tick bitmap. Each bit marks an initialized tick.
+pub fn read_tick_bitmap_word<T: StateProvider>(provider: T, pool_address: Address, word_pos: i16) -> eyre::Result<U256> {
+    Ok(TICK_BITMAP.entry(&word_pos).read(&provider, pool_address)?)
+}
+
+/// Read all initialized ticks of a pool by walking the tick bitmap.
+pub fn read_ticks<T: StateProvider>(provider: T, pool_address: Address, tick_spacing: i32) -> eyre::Result<BTreeMap<i32, TickInfo>> {
+    let min_word = MIN_TICK.div_euclid(tick_spacing) >> 8;
+    let max_word = MAX_TICK.div_euclid(tick_spacing) >> 8;
+
+    let mut ticks = BTreeMap::new();
+    for word_pos in min_word..=max_word {
+        let word = read_tick_bitmap_word(&provider, pool_address, word_pos as i16)?;
+        if word.is_zero() {
+            continue;
+        }
+        for bit_pos in 0..256 {
+            if !word.bit(bit_pos) {
+                continue;
+            }
+            let tick = ((word_pos << 8) + bit_pos as i32) * tick_spacing;
+            let Some(tick_info) = read_tick(&provider, pool_address, I24::try_from(tick)?)? else {
+                return Err(DexSyncError::invalid_layout(pool_address, format!("initialized tick {} not found", tick)).into());
+            };
+            ticks.insert(tick, tick_info);
+        }
+    }
+    Ok(ticks)
+}
+
+pub fn read_tick<T: StateProvider>(provider: T, pool_address: Address, tick: I24) -> eyre::Result<Option<TickInfo>> {
+    let tick_info = TICKS.entry(&tick);
+    let Some(storage_value0) = read_storage(&provider, pool_address, tick_info.slot)? else {
+        return Ok(None);
+    };
+    // fee growth outside is zero for ticks initialized above the current tick, zero slots are not stored
+    let storage_value1 = read_storage(&provider, pool_address, slot_offset(tick_info.slot, 1))?.unwrap_or_default();
+    let storage_value2 = read_storage(&provider, pool_address, slot_offset(tick_info.slot, 2))?.unwrap_or_default();
+    let storage_value3 = read_required_storage(&provider, pool_address, slot_offset(tick_info.slot, 3))?;
+    Ok(Some(decode_tick_info([storage_value0, storage_value1, storage_value2, storage_value3])))
+}
+
+/// Decode the four slots of a `Tick.Info`.
+pub fn decode_tick_info(values: [StorageValue; 4]) -> TickInfo {
+    let layout = TickInfoSlots { slot: B256::ZERO };
+    TickInfo {
+        liquidity_gross: layout.liquidity_gross().decode(values[0]),
+        liquidity_net: layout.liquidity_net().decode(values[0]),
+        fee_growth_outside_0x128: layout.fee_growth_outside0_x128().decode(values[1]),
+        fee_growth_outside_1x128: layout.fee_growth_outside1_x128().decode(values[2]),
+        tick_cumulative_outside: layout.tick_cumulative_outside().decode(values[3]),
+        seconds_per_liquidity_outside_x128: layout.seconds_per_liquidity_outside_x128().decode(values[3]),
+        seconds_outside: layout.seconds_outside().decode(values[3]),
+        initialized: layout.initialized().decode(values[3]),
+    }
+}
+
+/// Reconstruct all ticks of a pool from its storage entries using the tick index instead of walking the tick bitmap.
+pub fn ticks_from_storage_entries(
+    pool_address: Address,
+    entries: &[StorageEntry],
+    tick_index: &TickIndex,
+) -> eyre::Result<BTreeMap<i32, TickInfo>> {
+    let values: HashMap<B256, StorageValue> = entries.iter().map(|entry| (entry.key, entry.value)).collect();
+    let mut ticks = BTreeMap::new();
+    for entry in entries {
+        let Some(tick) = tick_index.tick(&entry.key) else {
+            continue;
+        };
+        let storage_key = U256::from_be_slice(entry.key.as_slice());
+        let value_at = |offset: u64| values.get(&B256::from(storage_key + U256::from(offset))).copied();
+        let Some(storage_value3) = value_at(3) else {
+            return Err(DexSyncError::MissingSlot { address: pool_address, slot: B256::from(storage_key + U256::from(3)) }.into());
+        };
+        let tick_info = decode_tick_info([entry.value, value_at(1).unwrap_or_default(), value_at(2).unwrap_or_default(), storage_value3]);
+        ticks.insert(tick, tick_info);
+    }
+    Ok(ticks)
+}
+
+/// Read all ticks of a pool with a single walk over its `PlainStorageState` entries.
+pub fn read_ticks_from_storage<DB: Database>(db: &DB, pool_address: Address, tick_spacing: i32) -> eyre::Result<BTreeMap<i32, TickInfo>> {
+    let entries = read_all_storage_entries(db, pool_address)?;
+    ticks_from_storage_entries(pool_address, &entries, &tick_index(tick_spacing, None)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use alloy_primitives::{address, U256};
+    use reth_db::models::storage_sharded_key::StorageShardedKey;
+    use reth_db::models::BlockNumberAddress;
+    use reth_db::transaction::DbTxMut;
+    use reth_db::{tables, BlockNumberList};
     use reth_primitives::{Account, StorageEntry};
     use reth_stages::test_utils::TestStageDB;
     use std::str::FromStr;
@@ -162,6 +556,80 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_pool_globals() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pool_weth_usdc = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        test_db.insert_accounts_and_storages(vec![(
+            pool_weth_usdc,
+            (
+                Account::default(),
+                vec![
+                    StorageEntry::new(FEE_GROWTH_GLOBAL0_SLOT, U256::from(11)),
+                    StorageEntry::new(FEE_GROWTH_GLOBAL1_SLOT, U256::from(22)),
+                    StorageEntry::new(PROTOCOL_FEES_SLOT, (U256::from(5) << 128) | U256::from(3)),
+                ],
+            ),
+        )])?;
+
+        let globals = read_pool_globals(test_db.factory.latest()?, pool_weth_usdc)?;
+        assert_eq!(globals.fee_growth_global0_x128, U256::from(11));
+        assert_eq!(globals.fee_growth_global1_x128, U256::from(22));
+        assert_eq!(globals.protocol_fees0, U128::from(3));
+        assert_eq!(globals.protocol_fees1, U128::from(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_pool_position() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pool_weth_usdc = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        let owner = address!("000000000000000000000000000000000000beef");
+        let (tick_lower, tick_upper) = (I24::try_from(-60)?, I24::try_from(60)?);
+        let liquidity = 10u128.pow(18);
+        let q128 = U256::from(1) << 128;
+
+        let position_key = keccak256((owner, tick_lower, tick_upper).abi_encode_packed());
+        let storage_key = U256::from_be_slice(keccak256((position_key, POSITIONS_SLOT).abi_encode()).as_slice());
+        test_db.insert_accounts_and_storages(vec![(
+            pool_weth_usdc,
+            (
+                Account::default(),
+                vec![
+                    // slot0 at tick 0
+                    StorageEntry::new(B256::ZERO, U256::from(1) << 96),
+                    StorageEntry::new(FEE_GROWTH_GLOBAL0_SLOT, q128 * U256::from(3)),
+                    StorageEntry::new(B256::from(storage_key), U256::from(liquidity)),
+                    StorageEntry::new(B256::from(storage_key + U256::from(1)), q128),
+                    StorageEntry::new(B256::from(storage_key + U256::from(3)), (U256::from(5) << 128) | U256::from(3)),
+                ],
+            ),
+        )])?;
+
+        let positions = read_pool_positions(
+            test_db.factory.latest()?,
+            pool_weth_usdc,
+            &[(owner, tick_lower, tick_upper), (owner, tick_lower, I24::try_from(120)?)],
+        )?;
+        assert_eq!(positions[0].info.liquidity, U128::from(liquidity));
+        assert_eq!(positions[0].info.fee_growth_inside0_last_x128, q128);
+        assert_eq!(positions[0].info.tokens_owed0, U128::from(3));
+        assert_eq!(positions[0].info.tokens_owed1, U128::from(5));
+        assert_eq!(positions[1].info.liquidity, U128::ZERO);
+
+        // no ticks stored, the fee growth inside is the global fee growth
+        let value = positions[0].value(test_db.factory.latest()?)?;
+        assert_eq!(value.amount0, U256::from(2995354955910780u128));
+        assert_eq!(value.amount1, U256::from(2995354955910780u128));
+        assert_eq!(value.fees0, U256::from(2 * liquidity + 3));
+        assert_eq!(value.fees1, U256::from(5));
+
+        Ok(())
+    }
+
     #[test]
     fn test_read_slot0() -> eyre::Result<()> {
         let test_db = TestStageDB::default();
@@ -192,6 +660,83 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_pools_history() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pool_weth_usdc = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        let slot0_before = U256::from_be_slice(b256!("00010002d302d301800307320000000000004f96a4fc64ac43f93680a947bbda").as_slice());
+        let slot0_after = U256::from_be_slice(b256!("00010002d302d301810307330000000000004f96a4fc64ac43f93680a947bbdb").as_slice());
+
+        // slot0 changes in block 10, liquidity in block 10 and 20
+        test_db.insert_accounts_and_storages(vec![(
+            pool_weth_usdc,
+            (Account::default(), vec![StorageEntry::new(B256::ZERO, slot0_after), StorageEntry::new(LIQUIDITY_SLOT, U256::from(300))]),
+        )])?;
+        test_db.commit(|tx| {
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pool_weth_usdc, B256::ZERO, u64::MAX),
+                BlockNumberList::new_pre_sorted([10]),
+            )?;
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pool_weth_usdc, LIQUIDITY_SLOT, u64::MAX),
+                BlockNumberList::new_pre_sorted([10, 20]),
+            )?;
+            tx.put::<tables::StorageChangeSets>(BlockNumberAddress((10, pool_weth_usdc)), StorageEntry::new(B256::ZERO, slot0_before))?;
+            tx.put::<tables::StorageChangeSets>(
+                BlockNumberAddress((10, pool_weth_usdc)),
+                StorageEntry::new(LIQUIDITY_SLOT, U256::from(100)),
+            )?;
+            tx.put::<tables::StorageChangeSets>(
+                BlockNumberAddress((20, pool_weth_usdc)),
+                StorageEntry::new(LIQUIDITY_SLOT, U256::from(200)),
+            )?;
+            Ok(())
+        })?;
+
+        let history = read_pools_history(test_db.factory.db_ref(), &[pool_weth_usdc], 0..=100)?;
+        assert_eq!(history.len(), 1);
+        let history = &history[0];
+        assert_eq!(history.initial_slot0, Some(decode_slot0(slot0_before)));
+        assert_eq!(history.initial_liquidity, U128::from(100));
+        assert_eq!(history.changes.len(), 2);
+        assert_eq!(history.changes[0].block_number, 10);
+        assert_eq!(history.changes[0].slot0, Some(decode_slot0(slot0_after)));
+        assert_eq!(history.changes[0].liquidity, U128::from(200));
+        assert_eq!(history.changes[1].block_number, 20);
+        assert_eq!(history.changes[1].slot0, Some(decode_slot0(slot0_after)));
+        assert_eq!(history.changes[1].liquidity, U128::from(300));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ticks() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pool_weth_usdc = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+
+        // tick 100 is bit 100 in word 0 and tick -10 is bit 255 in word -1 for a tick spacing of 10
+        let mut storage = vec![
+            StorageEntry::new(keccak256((0i16, TICK_BITMAP_SLOT).abi_encode()), U256::from(1) << 10),
+            StorageEntry::new(keccak256((-1i16, TICK_BITMAP_SLOT).abi_encode()), U256::from(1) << 255),
+        ];
+        for tick in [100, -10] {
+            let storage_key0 = keccak256((I24::try_from(tick)?, TICKS_SLOT).abi_encode());
+            let storage_key0 = U256::from_be_slice(storage_key0.as_slice());
+            storage.push(StorageEntry::new(B256::from(storage_key0), U256::from(tick.unsigned_abs())));
+            storage.push(StorageEntry::new(B256::from(storage_key0 + U256::from(3)), U256::from(1) << 248));
+        }
+        test_db.insert_accounts_and_storages(vec![(pool_weth_usdc, (Account::default(), storage))])?;
+
+        let ticks = read_ticks(test_db.factory.latest()?, pool_weth_usdc, 10)?;
+        assert_eq!(ticks.keys().copied().collect::<Vec<_>>(), vec![-10, 100]);
+        assert_eq!(ticks[&-10].liquidity_gross, U128::from(10));
+        assert_eq!(ticks[&100].liquidity_gross, U128::from(100));
+        assert!(ticks[&100].initialized);
+
+        Ok(())
+    }
+
     #[test]
     pub fn test_read_tick() -> eyre::Result<()> {
         let test_db = TestStageDB::default();
@@ -234,4 +779,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_ticks_from_storage() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pool_weth_usdc = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        let mut storage = vec![
+            StorageEntry::new(
+                b256!("ad66b8e7ab72f450ddfdaf1c5bc10e3a3fabf9f63ad8aa07b8743b93722f0a45"),
+                U256::from_be_slice(b256!("0000000000000000000000000000006400000000000000000000000000000064").as_slice()),
+            ),
+            StorageEntry::new(
+                b256!("ad66b8e7ab72f450ddfdaf1c5bc10e3a3fabf9f63ad8aa07b8743b93722f0a46"),
+                U256::from_be_slice(b256!("00000000000000000000000000000000000014c5771d23059d2718a51e7b0788").as_slice()),
+            ),
+            StorageEntry::new(
+                b256!("ad66b8e7ab72f450ddfdaf1c5bc10e3a3fabf9f63ad8aa07b8743b93722f0a47"),
+                U256::from_be_slice(b256!("000000000000000000000000000001ffe4d52ef99b7ca0682ff8350300adffbf").as_slice()),
+            ),
+            StorageEntry::new(
+                b256!("ad66b8e7ab72f450ddfdaf1c5bc10e3a3fabf9f63ad8aa07b8743b93722f0a48"),
+                U256::from_be_slice(b256!("0161058d820000000000000001e64843cf94d43eefe288859700015a733ba9cd").as_slice()),
+            ),
+        ];
+        // slot0 is not a tick
+        storage.push(StorageEntry::new(B256::ZERO, U256::from(1) << 96));
+        test_db.insert_accounts_and_storages(vec![(pool_weth_usdc, (Account::default(), storage))])?;
+
+        let ticks = read_ticks_from_storage(test_db.factory.db_ref(), pool_weth_usdc, 10)?;
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[&100], read_tick(test_db.factory.latest()?, pool_weth_usdc, I24::try_from(100).unwrap())?.unwrap());
+
+        Ok(())
+    }
 }
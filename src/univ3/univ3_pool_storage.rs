@@ -0,0 +1,187 @@
+use crate::univ3::ticks::{tick_index, TickIndex};
+use crate::univ3::univ3_math::{MAX_TICK, MIN_TICK};
+use crate::univ3::univ3_pool::{
+    decode_liquidity, decode_protocol_fees, decode_slot0, decode_tick_info, Univ3PoolGlobals, Univ3PoolState, FEE_GROWTH_GLOBAL0_SLOT,
+    FEE_GROWTH_GLOBAL1_SLOT, LIQUIDITY_SLOT, PROTOCOL_FEES_SLOT, TICK_BITMAP,
+};
+use crate::utils::{read_all_storage_entries, DexSyncError};
+use alloy_primitives::aliases::{I56, U24};
+use alloy_primitives::{Address, StorageValue, B256, U160, U256};
+use reth_db::Database;
+use reth_primitives::StorageEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const OBSERVATIONS_SLOT: u64 = 8;
+const OBSERVATIONS_LEN: u64 = 65535;
+
+/// An entry of the pool's oracle observations array.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: I56,
+    pub seconds_per_liquidity_cumulative_x128: U160,
+    pub initialized: bool,
+}
+
+/// Pool storage that is not needed to quote swaps, classified by the pool layout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Univ3PoolStorage {
+    pub globals: Univ3PoolGlobals,
+    pub tick_bitmap: BTreeMap<i16, U256>,
+    pub observations: BTreeMap<u16, Observation>,
+    /// Remaining hashed slots, the `positions` mapping is the only other mapping of the pool
+    pub positions: Vec<StorageEntry>,
+}
+
+/// Decode the packed observation slot `(bool initialized, uint160 secondsPerLiquidityCumulativeX128, int56 tickCumulative, uint32 blockTimestamp)`.
+pub fn decode_observation(value: StorageValue) -> Observation {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    Observation {
+        initialized: bytes[0] != 0,
+        seconds_per_liquidity_cumulative_x128: U160::from_be_slice(&bytes[1..21]),
+        tick_cumulative: I56::try_from_be_slice(&bytes[21..28]).unwrap(),
+        block_timestamp: u32::from_be_bytes([bytes[28], bytes[29], bytes[30], bytes[31]]),
+    }
+}
+
+impl Univ3PoolState {
+    /// Build the complete state of a pool from all its storage entries in a single pass. Returns `None` if slot0 is missing.
+    pub fn from_storage_entries(
+        address: Address,
+        fee: U24,
+        tick_spacing: i32,
+        entries: &[StorageEntry],
+        tick_index: &TickIndex,
+    ) -> eyre::Result<Option<(Self, Univ3PoolStorage)>> {
+        if tick_index.tick_spacing() != tick_spacing {
+            let reason = format!("tick index of spacing {} for tick spacing {}", tick_index.tick_spacing(), tick_spacing);
+            return Err(DexSyncError::invalid_layout(address, reason).into());
+        }
+        let bitmap_words = tick_bitmap_storage_keys(tick_spacing);
+        let values: HashMap<B256, StorageValue> = entries.iter().map(|entry| (entry.key, entry.value)).collect();
+
+        let mut slot0 = None;
+        let mut liquidity = None;
+        let mut ticks = BTreeMap::new();
+        let mut tick_slots = HashSet::new();
+        let mut storage = Univ3PoolStorage::default();
+        let mut hashed = vec![];
+
+        for entry in entries {
+            let slot = U256::from_be_slice(entry.key.as_slice());
+            if slot < U256::from(OBSERVATIONS_SLOT + OBSERVATIONS_LEN) {
+                match entry.key {
+                    B256::ZERO => slot0 = Some(decode_slot0(entry.value)),
+                    FEE_GROWTH_GLOBAL0_SLOT => storage.globals.fee_growth_global0_x128 = entry.value,
+                    FEE_GROWTH_GLOBAL1_SLOT => storage.globals.fee_growth_global1_x128 = entry.value,
+                    PROTOCOL_FEES_SLOT => {
+                        (storage.globals.protocol_fees0, storage.globals.protocol_fees1) = decode_protocol_fees(entry.value)
+                    }
+                    LIQUIDITY_SLOT => liquidity = Some(decode_liquidity(entry.value)),
+                    _ if slot >= U256::from(OBSERVATIONS_SLOT) => {
+                        let index = (slot - U256::from(OBSERVATIONS_SLOT)).to::<u16>();
+                        storage.observations.insert(index, decode_observation(entry.value));
+                    }
+                    _ => hashed.push(*entry),
+                }
+            } else if let Some(tick) = tick_index.tick(&entry.key) {
+                let value_at = |offset: u64| values.get(&B256::from(slot + U256::from(offset))).copied();
+                let Some(storage_value3) = value_at(3) else {
+                    return Err(DexSyncError::MissingSlot { address, slot: B256::from(slot + U256::from(3)) }.into());
+                };
+                ticks.insert(
+                    tick,
+                    decode_tick_info([entry.value, value_at(1).unwrap_or_default(), value_at(2).unwrap_or_default(), storage_value3]),
+                );
+                tick_slots.extend((1..=3).map(|offset| B256::from(slot + U256::from(offset))));
+            } else if let Some(word_pos) = bitmap_words.get(&entry.key) {
+                storage.tick_bitmap.insert(*word_pos, entry.value);
+            } else {
+                hashed.push(*entry);
+            }
+        }
+        storage.positions = hashed.into_iter().filter(|entry| !tick_slots.contains(&entry.key)).collect();
+
+        let Some(slot0) = slot0 else {
+            return Ok(None);
+        };
+        let pool_state = Univ3PoolState { address, fee, tick_spacing, slot0, liquidity: liquidity.unwrap_or_default(), ticks };
+        Ok(Some((pool_state, storage)))
+    }
+}
+
+/// Read the complete state of a pool with a single walk over its `PlainStorageState` entries.
+pub fn read_pool_state_from_storage<DB: Database>(
+    db: &DB,
+    pool_address: Address,
+    fee: U24,
+    tick_spacing: i32,
+) -> eyre::Result<Option<(Univ3PoolState, Univ3PoolStorage)>> {
+    let entries = read_all_storage_entries(db, pool_address)?;
+    Univ3PoolState::from_storage_entries(pool_address, fee, tick_spacing, &entries, &tick_index(tick_spacing, None)?)
+}
+
+fn tick_bitmap_storage_keys(tick_spacing: i32) -> HashMap<B256, i16> {
+    let min_word = MIN_TICK.div_euclid(tick_spacing) >> 8;
+    let max_word = MAX_TICK.div_euclid(tick_spacing) >> 8;
+    (min_word..=max_word).map(|word_pos| (TICK_BITMAP.entry_slot(&(word_pos as i16)), word_pos as i16)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::univ3::ticks::tick_storage_key;
+    use crate::univ3::univ3_pool::TICK_BITMAP_SLOT;
+    use crate::univ3::{read_pool_state, read_ticks};
+    use alloy_primitives::aliases::I24;
+    use alloy_primitives::{address, keccak256, U128};
+    use alloy_sol_types::SolValue;
+    use reth_primitives::Account;
+    use reth_stages::test_utils::TestStageDB;
+
+    #[test]
+    fn test_read_pool_state_from_storage() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pool_address = address!("88e6a0c2ddd26feeb64f039a2c41296fcb3f5640");
+        let tick_spacing = 10;
+
+        let tick_key = U256::from_be_slice(tick_storage_key(I24::try_from(100)?).as_slice());
+        let position_key = keccak256(b"position");
+        let storage = vec![
+            StorageEntry::new(B256::ZERO, U256::from(1) << 96),
+            StorageEntry::new(FEE_GROWTH_GLOBAL0_SLOT, U256::from(11)),
+            StorageEntry::new(PROTOCOL_FEES_SLOT, (U256::from(5) << 128) | U256::from(3)),
+            StorageEntry::new(LIQUIDITY_SLOT, U256::from(1000)),
+            // tick 100 with liquidity 100, initialized
+            StorageEntry::new(B256::from(tick_key), (U256::from(100) << 128) | U256::from(100)),
+            StorageEntry::new(B256::from(tick_key + U256::from(1)), U256::from(7)),
+            StorageEntry::new(B256::from(tick_key + U256::from(3)), U256::from(1) << 248),
+            // bit 10 of word 0
+            StorageEntry::new(keccak256((0i16, TICK_BITMAP_SLOT).abi_encode()), U256::from(1) << 10),
+            // observation 1 at timestamp 42, initialized
+            StorageEntry::new(B256::from(U256::from(OBSERVATIONS_SLOT + 1)), (U256::from(1) << 248) | U256::from(42)),
+            StorageEntry::new(position_key, U256::from(1)),
+        ];
+        test_db.insert_accounts_and_storages(vec![(pool_address, (Account::default(), storage))])?;
+
+        let (pool_state, pool_storage) =
+            read_pool_state_from_storage(test_db.factory.db_ref(), pool_address, U24::from(500), tick_spacing)?.unwrap();
+        assert_eq!(pool_state.liquidity, U128::from(1000));
+        assert_eq!(pool_state.ticks, read_ticks(test_db.factory.latest()?, pool_address, tick_spacing)?);
+        assert_eq!(pool_state.ticks[&100].fee_growth_outside_0x128, U256::from(7));
+        let expected = read_pool_state(test_db.factory.latest()?, pool_address, U24::from(500), tick_spacing)?.unwrap();
+        assert_eq!(pool_state.slot0, expected.slot0);
+
+        assert_eq!(pool_storage.globals.fee_growth_global0_x128, U256::from(11));
+        assert_eq!(pool_storage.globals.protocol_fees0, U128::from(3));
+        assert_eq!(pool_storage.globals.protocol_fees1, U128::from(5));
+        assert_eq!(pool_storage.tick_bitmap, BTreeMap::from([(0, U256::from(1) << 10)]));
+        assert_eq!(pool_storage.observations.len(), 1);
+        assert_eq!(pool_storage.observations[&1].block_timestamp, 42);
+        assert!(pool_storage.observations[&1].initialized);
+        assert_eq!(pool_storage.positions, vec![StorageEntry::new(position_key, U256::from(1))]);
+
+        Ok(())
+    }
+}
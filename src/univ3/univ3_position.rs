@@ -1,100 +1,363 @@
-use crate::univ3::univ3_pool::{read_liquidity, Univ3Pool};
-use crate::univ3::{read_slot0, Univ3Slot0};
-use alloy_primitives::aliases::{U176, U24, U80};
-use alloy_primitives::{address, b256, keccak256, Address, B256, U128, U256};
+use crate::storage_struct;
+use crate::univ3::univ3_pool::{read_liquidity, read_position_value, PositionInfo, PositionValue, Univ3Pool};
+use crate::univ3::{read_slot0, Univ3Slot0, UNI_V3_FACTORY};
+use crate::utils::chunk_reader::ChunkReader;
+use crate::utils::progress::{LoadPhase, NoopProgress, ProgressObserver, ProgressTracker};
+use crate::utils::telemetry::{record_pools_loaded, record_read_duration};
+use crate::utils::{
+    missing_slot, read_required_storage, read_storage, resolve_block, slot_offset, state_provider, DexSyncError, DynArray, Field,
+    LoadChunk, LoadFailure, LoadMode, Mapping, PoolDeployment, PoolStream,
+};
+use alloy::eips::{BlockNumHash, BlockNumberOrTag};
+use alloy_primitives::aliases::{I24, U176, U24, U80, U96};
+use alloy_primitives::{b256, keccak256, Address, StorageValue, B256, U128, U256};
 use alloy_sol_types::SolValue;
-use eyre::eyre;
-use reth_provider::StateProvider;
+use reth_provider::{StateProvider, StateProviderFactory};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::debug;
 
-const POOL_INIT_CODE_HASH: B256 = b256!("e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54");
+/// Init code hash of the Uniswap V3 pool
+pub const POOL_INIT_CODE_HASH: B256 = b256!("e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54");
+const TOKEN_OWNERS_ENTRIES: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000002");
 const NEXT_POOL_ID: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000d");
+const POOL_IDS_SLOT: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000a");
 const POOL_ID_TO_POOL_KEY: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000b");
+const POSITIONS: B256 = b256!("000000000000000000000000000000000000000000000000000000000000000c");
+/// Pools read with one state provider, a long-running transaction fails
+const CHUNK_SIZE: usize = 1000;
+
+storage_struct! {
+    /// `PoolAddress.PoolKey`
+    struct PoolKeySlots[2] {
+        token0: Address => (0, 0),
+        token1: Address => (1, 0),
+        fee: U24 => (1, 20),
+    }
+}
+
+storage_struct! {
+    /// `NonfungiblePositionManager.Position`
+    struct PositionSlots[5] {
+        nonce: U96 => (0, 0),
+        operator: Address => (0, 12),
+        pool_id: U80 => (1, 0),
+        tick_lower: I24 => (1, 10),
+        tick_upper: I24 => (1, 13),
+        liquidity: U128 => (1, 16),
+        fee_growth_inside0_last_x128: U256 => (2, 0),
+        fee_growth_inside1_last_x128: U256 => (3, 0),
+        tokens_owed0: U128 => (4, 0),
+        tokens_owed1: U128 => (4, 16),
+    }
+}
+
+storage_struct! {
+    /// `EnumerableMap.MapEntry` of the token owners
+    struct TokenOwnerSlots[2] {
+        token_id: U256 => (0, 0),
+        owner: Address => (1, 0),
+    }
+}
+
+/// `mapping(address => uint80) _poolIds`
+const POOL_IDS: Mapping<Address, Field<U80>> = Mapping::new(POOL_IDS_SLOT);
+/// `mapping(uint80 => PoolAddress.PoolKey) _poolIdToPoolKey`
+const POOL_KEYS: Mapping<U80, PoolKeySlots> = Mapping::new(POOL_ID_TO_POOL_KEY);
+/// `mapping(uint256 => Position) _positions`
+const POSITION_ENTRIES: Mapping<U256, PositionSlots> = Mapping::new(POSITIONS);
+/// `MapEntry[] _entries` of `EnumerableMap.UintToAddressMap _tokenOwners`
+const TOKEN_OWNERS: DynArray<TokenOwnerSlots> = DynArray::new(TOKEN_OWNERS_ENTRIES);
 
 #[derive(Debug)]
 pub struct PoolKey {
-    token0: Address,
-    token1: Address,
-    fee: U24,
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: U24,
 }
 
 #[derive(Debug)]
 pub struct UniV3PositionManager {
     pub pools: Vec<(Univ3Pool, Univ3Slot0, U128)>,
+    /// Pools skipped by a lenient load
+    pub failures: Vec<LoadFailure>,
+    /// Block the pools were read at, `Latest` is resolved once at the start of the load
+    pub block: BlockNumHash,
 }
 
 impl UniV3PositionManager {
-    pub fn load_pools<T: StateProvider>(provider: T, univ3_position_mng: Address) -> eyre::Result<Self> {
-        let pools = read_univ3_position_pools(&provider, univ3_position_mng)?;
-        let mut result = vec![];
-        for pool in pools {
-            let slot0 = match read_slot0(&provider, pool.address)? {
-                None => {
-                    return Err(eyre!("Failed to read slot0: {:#?}", pool.address));
-                }
-                Some(slot0) => slot0,
-            };
-
-            let liquidity = read_liquidity(&provider, pool.address)?;
-
-            result.push((pool, slot0, liquidity));
+    /// Load all pools of the position manager with slot0 and liquidity at the block.
+    pub fn load_pools<P: StateProviderFactory>(
+        provider_factory: &P,
+        block_number_or_tag: &BlockNumberOrTag,
+        univ3_position_mng: Address,
+    ) -> eyre::Result<Self> {
+        Self::load_pools_with_progress(provider_factory, block_number_or_tag, univ3_position_mng, LoadMode::Strict, &NoopProgress)
+    }
+
+    /// Same as `load_pools` with the load mode, notifying the observer after each chunk of pools with slot0 and liquidity read.
+    pub fn load_pools_with_progress<P: StateProviderFactory>(
+        provider_factory: &P,
+        block_number_or_tag: &BlockNumberOrTag,
+        univ3_position_mng: Address,
+        mode: LoadMode,
+        observer: &dyn ProgressObserver,
+    ) -> eyre::Result<Self> {
+        let started = Instant::now();
+        let block = resolve_block(provider_factory, block_number_or_tag)?;
+        let provider = state_provider(provider_factory, &BlockNumberOrTag::Number(block.number))?;
+        // pool ids start at 1
+        let total = read_next_pool_id(&provider, univ3_position_mng)?.to::<usize>().saturating_sub(1);
+
+        let progress = ProgressTracker::new(observer, LoadPhase::Pools, total);
+        let mut pools = vec![];
+        let mut failures = vec![];
+        let reader = ChunkReader {
+            block_number_or_tag: BlockNumberOrTag::Number(block.number),
+            chunk_size: CHUNK_SIZE,
+            mode,
+            metric: "univ3_pools_chunk",
+        };
+        reader.read(
+            provider_factory,
+            1..total + 1,
+            |_| univ3_position_mng,
+            |provider, pool_id| read_pool_with_state(provider, univ3_position_mng, pool_id).map(Some),
+            |chunk, chunk_failures, end| {
+                pools.extend(chunk);
+                failures.extend(chunk_failures);
+                progress.update(end - 1, end - 1);
+                true
+            },
+        )?;
+        record_read_duration("univ3_pools", started.elapsed());
+        record_pools_loaded("uniswap_v3", pools.len());
+        Ok(UniV3PositionManager { pools, failures, block })
+    }
+
+    /// Drop the pools not deployed by the factory of the deployment or running other code, and record them as failures. The
+    /// index of a failure is the position in `pools` before the check.
+    pub fn retain_verified_pools<T: StateProvider>(&mut self, provider: T, deployment: &PoolDeployment) -> eyre::Result<()> {
+        let pools = std::mem::take(&mut self.pools);
+        for (idx, (pool, slot0, liquidity)) in pools.into_iter().enumerate() {
+            let verified = deployment.verify(&provider, pool.address, pool.salt()).map_err(eyre::Report::from);
+            if LoadMode::Lenient.check(verified, pool.address, idx, &mut self.failures)?.is_some() {
+                self.pools.push((pool, slot0, liquidity));
+            }
         }
-        Ok(UniV3PositionManager { pools: result })
+        Ok(())
+    }
+
+    /// Stream the pools of the position manager with slot0 and liquidity in chunks as they are read. Pools skipped in lenient
+    /// mode are the failures of their chunk. Must be called within a tokio runtime.
+    pub fn stream_pools<P: StateProviderFactory + Send + Sync + 'static>(
+        provider_factory: Arc<P>,
+        block_number_or_tag: BlockNumberOrTag,
+        univ3_position_mng: Address,
+        chunk_size: usize,
+        mode: LoadMode,
+    ) -> PoolStream<(Univ3Pool, Univ3Slot0, U128)> {
+        PoolStream::spawn(move |sender| {
+            let block = resolve_block(provider_factory.as_ref(), &block_number_or_tag)?;
+            let block_number_or_tag = BlockNumberOrTag::Number(block.number);
+            let provider = state_provider(provider_factory.as_ref(), &block_number_or_tag)?;
+            // pool ids start at 1
+            let total = read_next_pool_id(&provider, univ3_position_mng)?.to::<usize>().saturating_sub(1);
+
+            let reader = ChunkReader { block_number_or_tag, chunk_size, mode, metric: "univ3_pools_chunk" };
+            reader.read(
+                provider_factory.as_ref(),
+                1..total + 1,
+                |_| univ3_position_mng,
+                |provider, pool_id| read_pool_with_state(provider, univ3_position_mng, pool_id).map(Some),
+                |items, failures, end| {
+                    record_pools_loaded("uniswap_v3", items.len());
+                    sender.send(LoadChunk { items, failures, done: end - 1, total, block })
+                },
+            )
+        })
     }
 }
 
-pub fn read_univ3_position_pools<T: StateProvider>(provider: T, univ3_position_mng: Address) -> eyre::Result<Vec<Univ3Pool>> {
-    let univ3_factory = address!("1F98431c8aD98523631AE4a59f267346ea31F984");
-
-    let (next_pool_id, next_position_id) = match provider.storage(univ3_position_mng, NEXT_POOL_ID)? {
-        None => return Err(eyre!("Invalid pair length")),
-        Some(value) => {
-            let bytes = value.to_be_bytes_vec();
-            let next_pool_id = U80::from_be_slice(&bytes[0..10]);
-            let next_position_id = U176::from_be_slice(&bytes[10..32]);
-            (next_pool_id, next_position_id)
-        }
+/// Read the pool of a pool id with slot0 and liquidity.
+fn read_pool_with_state<T: StateProvider>(
+    provider: T,
+    univ3_position_mng: Address,
+    pool_id: usize,
+) -> eyre::Result<(Univ3Pool, Univ3Slot0, U128)> {
+    let pool_key = read_pool_key(&provider, univ3_position_mng, U80::from(pool_id))?;
+    let address = compute_address(UNI_V3_FACTORY, &pool_key)?;
+    let Some(slot0) = read_slot0(&provider, address)? else {
+        return Err(missing_slot(&provider, address, B256::ZERO).into());
     };
+    let liquidity = read_liquidity(&provider, address)?;
+    Ok((Univ3Pool { address, token0: pool_key.token0, token1: pool_key.token1, fee: pool_key.fee }, slot0, liquidity))
+}
+
+/// Read `_nextPoolId` of the position manager, packed with `_nextId` in one slot.
+fn read_next_pool_id<T: StateProvider>(provider: T, univ3_position_mng: Address) -> eyre::Result<U80> {
+    let value = read_required_storage(&provider, univ3_position_mng, NEXT_POOL_ID)?;
+    Ok(U80::from_be_slice(&value.to_be_bytes::<32>()[0..10]))
+}
+
+/// A position of the `NonfungiblePositionManager` with the owner of the token and the address of its pool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftPosition {
+    pub token_id: U256,
+    pub owner: Address,
+    pub pool: Address,
+    pub nonce: U96,
+    pub operator: Address,
+    pub pool_id: U80,
+    pub tick_lower: I24,
+    pub tick_upper: I24,
+    pub info: PositionInfo,
+}
+
+impl NftPosition {
+    /// Underlying token amounts and uncollected fees at the state of the provider.
+    pub fn value<T: StateProvider>(&self, provider: T) -> eyre::Result<PositionValue> {
+        read_position_value(provider, self.pool, self.tick_lower, self.tick_upper, &self.info)
+    }
+}
+
+pub fn read_univ3_position_pools<T: StateProvider>(provider: T, univ3_position_mng: Address) -> eyre::Result<Vec<Univ3Pool>> {
+    let bytes = read_required_storage(&provider, univ3_position_mng, NEXT_POOL_ID)?.to_be_bytes_vec();
+    let next_pool_id = U80::from_be_slice(&bytes[0..10]);
+    let next_position_id = U176::from_be_slice(&bytes[10..32]);
     debug!("Next pool id: {}, Next position id: {}", next_pool_id, next_position_id);
 
     let mut pool_addresses = vec![];
 
     for pool_id in 1..next_pool_id.to::<u64>() {
-        // mapping(uint80 => PoolAddress.PoolKey)
-        let storage_key0 = keccak256((U80::from(pool_id), POOL_ID_TO_POOL_KEY).abi_encode());
-        let storage_key1 = B256::from(U256::from_be_slice(storage_key0.0.as_slice()) + U256::from(1));
-
-        let pool_key = match provider.storage(univ3_position_mng, storage_key0)? {
-            None => return Err(eyre!("Invalid pool id")),
-            Some(value) => {
-                let bytes = value.to_be_bytes_vec();
-                let token0 = Address::from_slice(&bytes[12..32]);
-
-                // read second slot
-                let (fee, token1) = match provider.storage(univ3_position_mng, storage_key1)? {
-                    None => return Err(eyre!("Invalid pool id second slot")),
-                    Some(value) => {
-                        let bytes = value.to_be_bytes_vec();
-                        let fee = U24::from_be_slice(&bytes[9..12]);
-                        let token1 = Address::from_slice(&bytes[12..32]);
-                        (fee, token1)
-                    }
-                };
-
-                PoolKey { token0, token1, fee }
-            }
-        };
-
-        let pool_address = compute_address(univ3_factory, &pool_key)?;
+        let pool_key = read_pool_key(&provider, univ3_position_mng, U80::from(pool_id))?;
+        let pool_address = compute_address(UNI_V3_FACTORY, &pool_key)?;
         pool_addresses.push(Univ3Pool { address: pool_address, token0: pool_key.token0, token1: pool_key.token1, fee: pool_key.fee });
     }
 
     Ok(pool_addresses)
 }
 
+/// Read the pool key of a pool id from `_poolIdToPoolKey`.
+pub fn read_pool_key<T: StateProvider>(provider: T, univ3_position_mng: Address, pool_id: U80) -> eyre::Result<PoolKey> {
+    let pool_key = POOL_KEYS.entry(&pool_id);
+    let token0 = pool_key.token0().read_required(&provider, univ3_position_mng)?;
+    // token1 and fee share the second slot
+    let value = read_required_storage(&provider, univ3_position_mng, pool_key.token1().slot)?;
+    Ok(PoolKey { token0, token1: pool_key.token1().decode(value), fee: pool_key.fee().decode(value) })
+}
+
+/// Read the pool key of a pool address from `_poolIds`. Returns `None` if the pool was never used by the position manager.
+pub fn read_pool_key_by_address<T: StateProvider>(
+    provider: T,
+    univ3_position_mng: Address,
+    pool: Address,
+) -> eyre::Result<Option<PoolKey>> {
+    let pool_id = POOL_IDS.entry(&pool).read(&provider, univ3_position_mng)?;
+    if pool_id.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(read_pool_key(&provider, univ3_position_mng, pool_id)?))
+}
+
+/// Read all positions of the position manager by enumerating the token owners of the ERC721.
+pub fn read_nft_positions<T: StateProvider>(provider: T, univ3_position_mng: Address) -> eyre::Result<Vec<NftPosition>> {
+    let token_count = TOKEN_OWNERS.read_len(&provider, univ3_position_mng)?;
+
+    let mut pool_addresses = HashMap::new();
+    let mut positions = Vec::with_capacity(token_count);
+    for idx in 0..token_count {
+        let entry = TOKEN_OWNERS.item(idx);
+        let token_id = entry.token_id().read_required(&provider, univ3_position_mng)?;
+        let owner = entry.owner().read(&provider, univ3_position_mng)?;
+        positions.push(read_nft_position(&provider, univ3_position_mng, token_id, owner, &mut pool_addresses)?);
+    }
+    Ok(positions)
+}
+
+/// Read the `_positions` entry of a token and resolve its pool. The pool addresses are cached by pool id.
+fn read_nft_position<T: StateProvider>(
+    provider: T,
+    univ3_position_mng: Address,
+    token_id: U256,
+    owner: Address,
+    pool_addresses: &mut HashMap<U80, Address>,
+) -> eyre::Result<NftPosition> {
+    let position = POSITION_ENTRIES.entry(&token_id);
+    let read_slot = |offset: u64| -> eyre::Result<StorageValue> {
+        Ok(read_storage(&provider, univ3_position_mng, slot_offset(position.slot, offset))?.unwrap_or_default())
+    };
+
+    let value = read_slot(0)?;
+    let operator = position.operator().decode(value);
+    let nonce = position.nonce().decode(value);
+
+    let value = read_slot(1)?;
+    let liquidity = position.liquidity().decode(value);
+    let tick_upper = position.tick_upper().decode(value);
+    let tick_lower = position.tick_lower().decode(value);
+    let pool_id = position.pool_id().decode(value);
+    if pool_id.is_zero() {
+        return Err(DexSyncError::MissingSlot { address: univ3_position_mng, slot: position.pool_id().slot }.into());
+    }
+
+    let fee_growth_inside0_last_x128 = position.fee_growth_inside0_last_x128().read(&provider, univ3_position_mng)?;
+    let fee_growth_inside1_last_x128 = position.fee_growth_inside1_last_x128().read(&provider, univ3_position_mng)?;
+
+    let value = read_slot(4)?;
+    let tokens_owed1 = position.tokens_owed1().decode(value);
+    let tokens_owed0 = position.tokens_owed0().decode(value);
+
+    let pool = match pool_addresses.get(&pool_id) {
+        Some(pool) => *pool,
+        None => {
+            let pool = compute_address(UNI_V3_FACTORY, &read_pool_key(&provider, univ3_position_mng, pool_id)?)?;
+            pool_addresses.insert(pool_id, pool);
+            pool
+        }
+    };
+
+    Ok(NftPosition {
+        token_id,
+        owner,
+        pool,
+        nonce,
+        operator,
+        pool_id,
+        tick_lower,
+        tick_upper,
+        info: PositionInfo { liquidity, fee_growth_inside0_last_x128, fee_growth_inside1_last_x128, tokens_owed0, tokens_owed1 },
+    })
+}
+
+/// Group positions by the owner of the token.
+pub fn group_positions_by_owner(positions: &[NftPosition]) -> BTreeMap<Address, Vec<&NftPosition>> {
+    let mut result: BTreeMap<Address, Vec<&NftPosition>> = BTreeMap::new();
+    for position in positions {
+        result.entry(position.owner).or_default().push(position);
+    }
+    result
+}
+
+/// Group positions by pool address.
+pub fn group_positions_by_pool(positions: &[NftPosition]) -> BTreeMap<Address, Vec<&NftPosition>> {
+    let mut result: BTreeMap<Address, Vec<&NftPosition>> = BTreeMap::new();
+    for position in positions {
+        result.entry(position.pool).or_default().push(position);
+    }
+    result
+}
+
 pub fn compute_address(factory: Address, key: &PoolKey) -> eyre::Result<Address> {
     if key.token0 >= key.token1 {
-        return Err(eyre!("token0 must be less than token1"));
+        return Err(DexSyncError::InvalidTokenPair {
+            token_a: key.token0,
+            token_b: key.token1,
+            reason: "token0 must be less than token1".to_string(),
+        }
+        .into());
     }
     let inner_hash = keccak256((key.token0, key.token1, key.fee).abi_encode());
     let pool_hash = keccak256((b"\xff", factory, inner_hash, POOL_INIT_CODE_HASH).abi_encode_packed());
@@ -106,7 +369,13 @@ pub fn compute_address(factory: Address, key: &PoolKey) -> eyre::Result<Address>
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy_primitives::address;
+    use crate::univ3::UNI_V3_POSITION_MANAGER;
+    use alloy_primitives::{address, U160};
+    use futures::StreamExt;
+    use reth_db::tables;
+    use reth_db::transaction::DbTxMut;
+    use reth_primitives::{Account, StorageEntry};
+    use reth_stages::test_utils::TestStageDB;
 
     #[test]
     fn test_compute_address() {
@@ -123,5 +392,166 @@ mod tests {
             Ok(pool_address) => assert_eq!(pool_address, expected_address),
             Err(e) => panic!("Failed to compute address: {:?}", e),
         }
+
+        let key = PoolKey { token0: key.token1, token1: key.token0, fee: key.fee };
+        let err = compute_address(factory, &key).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DexSyncError>(), Some(DexSyncError::InvalidTokenPair { .. })));
+    }
+
+    #[test]
+    fn test_read_nft_positions() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let uni = address!("1f9840a85d5af5bf1d1762f925bdaddc4201f984");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pool = address!("1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801");
+        let owner = address!("000000000000000000000000000000000000beef");
+        let token_id = U256::from(7);
+        let liquidity = 10u128.pow(18);
+
+        let entries_slot = U256::from_be_slice(keccak256(TOKEN_OWNERS_ENTRIES).as_slice());
+        let position_slot = U256::from_be_slice(keccak256((token_id, POSITIONS).abi_encode()).as_slice());
+        let pool_key_slot = U256::from_be_slice(keccak256((U80::from(1), POOL_ID_TO_POOL_KEY).abi_encode()).as_slice());
+        // liquidity | tickUpper 60 | tickLower -60 | poolId 1
+        let position_ticks = (U256::from(liquidity) << 128) | (U256::from(60) << 104) | (U256::from(0xffffc4) << 80) | U256::from(1);
+
+        test_db.insert_accounts_and_storages(vec![
+            (
+                UNI_V3_POSITION_MANAGER,
+                (
+                    Account::default(),
+                    vec![
+                        StorageEntry::new(TOKEN_OWNERS_ENTRIES, U256::from(1)),
+                        StorageEntry::new(B256::from(entries_slot), token_id),
+                        StorageEntry::new(B256::from(entries_slot + U256::from(1)), U256::from_be_slice(owner.as_slice())),
+                        StorageEntry::new(B256::from(position_slot + U256::from(1)), position_ticks),
+                        StorageEntry::new(B256::from(position_slot + U256::from(4)), (U256::from(5) << 128) | U256::from(3)),
+                        StorageEntry::new(B256::from(pool_key_slot), U256::from_be_slice(uni.as_slice())),
+                        StorageEntry::new(
+                            B256::from(pool_key_slot + U256::from(1)),
+                            (U256::from(3000) << 160) | U256::from_be_slice(weth.as_slice()),
+                        ),
+                    ],
+                ),
+            ),
+            // slot0 at tick 0
+            (pool, (Account::default(), vec![StorageEntry::new(B256::ZERO, U256::from(1) << 96)])),
+        ])?;
+
+        let positions = read_nft_positions(test_db.factory.latest()?, UNI_V3_POSITION_MANAGER)?;
+        assert_eq!(positions.len(), 1);
+        let position = &positions[0];
+        assert_eq!(position.token_id, token_id);
+        assert_eq!(position.owner, owner);
+        assert_eq!(position.pool, pool);
+        assert_eq!(position.pool_id, U80::from(1));
+        assert_eq!(position.tick_lower, I24::try_from(-60)?);
+        assert_eq!(position.tick_upper, I24::try_from(60)?);
+        assert_eq!(position.info.liquidity, U128::from(liquidity));
+        assert_eq!(position.info.tokens_owed0, U128::from(3));
+        assert_eq!(position.info.tokens_owed1, U128::from(5));
+
+        assert_eq!(group_positions_by_owner(&positions)[&owner].len(), 1);
+        assert_eq!(group_positions_by_pool(&positions)[&pool].len(), 1);
+
+        let value = position.value(test_db.factory.latest()?)?;
+        assert_eq!(value.amount0, U256::from(2995354955910780u128));
+        assert_eq!(value.amount1, U256::from(2995354955910780u128));
+        assert_eq!(value.fees0, U256::from(3));
+        assert_eq!(value.fees1, U256::from(5));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_pools() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let uni = address!("1f9840a85d5af5bf1d1762f925bdaddc4201f984");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pool = address!("1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801");
+        let pool_key_slot = U256::from_be_slice(keccak256((U80::from(1), POOL_ID_TO_POOL_KEY).abi_encode()).as_slice());
+
+        test_db.insert_accounts_and_storages(vec![
+            (
+                UNI_V3_POSITION_MANAGER,
+                (
+                    Account::default(),
+                    vec![
+                        // _nextPoolId 2 and _nextId 1
+                        StorageEntry::new(NEXT_POOL_ID, (U256::from(2) << 176) | U256::from(1)),
+                        StorageEntry::new(B256::from(pool_key_slot), U256::from_be_slice(uni.as_slice())),
+                        StorageEntry::new(
+                            B256::from(pool_key_slot + U256::from(1)),
+                            (U256::from(3000) << 160) | U256::from_be_slice(weth.as_slice()),
+                        ),
+                    ],
+                ),
+            ),
+            (pool, (Account::default(), vec![StorageEntry::new(B256::ZERO, U256::from(1) << 96)])),
+        ])?;
+        test_db.commit(|tx| Ok(tx.put::<tables::CanonicalHeaders>(0, B256::with_last_byte(1))?))?;
+
+        let stream = UniV3PositionManager::stream_pools(
+            Arc::new(test_db.factory.clone()),
+            BlockNumberOrTag::Latest,
+            UNI_V3_POSITION_MANAGER,
+            100,
+            LoadMode::Strict,
+        );
+        let chunks = stream.collect::<Vec<_>>().await.into_iter().collect::<eyre::Result<Vec<_>>>()?;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].done, chunks[0].total), (1, 1));
+        let (univ3_pool, slot0, liquidity) = &chunks[0].items[0];
+        assert_eq!(univ3_pool.address, pool);
+        assert_eq!(univ3_pool.fee, U24::from(3000));
+        assert_eq!(slot0.sqrt_price_x96, U160::from(1) << 96);
+        assert_eq!(*liquidity, U128::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_pools_lenient() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let uni = address!("1f9840a85d5af5bf1d1762f925bdaddc4201f984");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pool = address!("1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801");
+        let pool_key_slot = U256::from_be_slice(keccak256((U80::from(1), POOL_ID_TO_POOL_KEY).abi_encode()).as_slice());
+
+        // the pool of the pool key is not deployed
+        test_db.insert_accounts_and_storages(vec![(
+            UNI_V3_POSITION_MANAGER,
+            (
+                Account::default(),
+                vec![
+                    StorageEntry::new(NEXT_POOL_ID, (U256::from(2) << 176) | U256::from(1)),
+                    StorageEntry::new(B256::from(pool_key_slot), U256::from_be_slice(uni.as_slice())),
+                    StorageEntry::new(
+                        B256::from(pool_key_slot + U256::from(1)),
+                        (U256::from(3000) << 160) | U256::from_be_slice(weth.as_slice()),
+                    ),
+                ],
+            ),
+        )])?;
+
+        test_db.commit(|tx| Ok(tx.put::<tables::CanonicalHeaders>(0, B256::with_last_byte(1))?))?;
+
+        assert!(UniV3PositionManager::load_pools(&test_db.factory, &BlockNumberOrTag::Latest, UNI_V3_POSITION_MANAGER).is_err());
+
+        let position_manager = UniV3PositionManager::load_pools_with_progress(
+            &test_db.factory,
+            &BlockNumberOrTag::Latest,
+            UNI_V3_POSITION_MANAGER,
+            LoadMode::Lenient,
+            &NoopProgress,
+        )?;
+        assert!(position_manager.pools.is_empty());
+        assert_eq!(position_manager.block, BlockNumHash::new(0, B256::with_last_byte(1)));
+        assert_eq!(position_manager.failures.len(), 1);
+        assert_eq!((position_manager.failures[0].address, position_manager.failures[0].index), (pool, 1));
+
+        Ok(())
     }
 }
@@ -21,22 +21,35 @@ pub struct DexSyncCache {}
 impl DexSyncCache {
     /// Save data to a file
     pub fn save<T: Serialize>(path: &Path, address: Address, data: T) -> eyre::Result<()> {
+        Self::save_named(path, &format!("factory_{:#?}", address), data)
+    }
+
+    /// Save data to the file `<name>.bincode`, e.g. to store other data next to the factory cache.
+    pub fn save_named<T: Serialize>(path: &Path, name: &str, data: T) -> eyre::Result<()> {
         if !Path::new(&path).exists() {
             fs::create_dir_all(path)?;
         }
-        let mut file = fs::File::create(path.join(format!("factory_{:#?}.bincode", address)))?;
+        let mut file = fs::File::create(path.join(format!("{}.bincode", name)))?;
         let encoded: Vec<u8> = bincode::serialize(&data)?;
         file.write_all(&encoded)?;
         Ok(())
     }
 
-    /// Load data from a file
+    /// Load data from a file. Returns `CacheError::FileNotFound` if the file is missing, also inside an existing directory.
     pub fn load<T>(path: &Path, address: Address) -> Result<T, CacheError>
     where
         T: DeserializeOwned,
     {
-        let file_path = path.join(format!("factory_{:#?}.bincode", address));
-        if !path.exists() {
+        Self::load_named(path, &format!("factory_{:#?}", address))
+    }
+
+    /// Load data from the file `<name>.bincode`. A missing file is `CacheError::FileNotFound`, not `CacheError::Io`.
+    pub fn load_named<T>(path: &Path, name: &str) -> Result<T, CacheError>
+    where
+        T: DeserializeOwned,
+    {
+        let file_path = path.join(format!("{}.bincode", name));
+        if !file_path.exists() {
             return Err(CacheError::FileNotFound);
         }
         let encoded = fs::read(file_path)?;
@@ -44,3 +57,22 @@ impl DexSyncCache {
         Ok(decoded)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_file() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(format!("dexsync_cache_{}", std::process::id()));
+        assert!(matches!(DexSyncCache::load_named::<u32>(&path, "missing"), Err(CacheError::FileNotFound)));
+
+        // the directory exists, the file does not
+        DexSyncCache::save_named(&path, "saved", 7u32)?;
+        assert_eq!(DexSyncCache::load_named::<u32>(&path, "saved")?, 7);
+        assert!(matches!(DexSyncCache::load_named::<u32>(&path, "missing"), Err(CacheError::FileNotFound)));
+
+        fs::remove_dir_all(&path)?;
+        Ok(())
+    }
+}
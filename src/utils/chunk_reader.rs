@@ -0,0 +1,52 @@
+use crate::utils::telemetry::record_read_duration;
+use crate::utils::{state_provider, LoadFailure, LoadMode};
+use alloy::eips::BlockNumberOrTag;
+use alloy_primitives::Address;
+use reth_provider::{StateProviderBox, StateProviderFactory};
+use std::ops::Range;
+use std::time::Instant;
+
+/// Reads the items of an index range in chunks, e.g. the pairs of a factory or the pools of the position manager. Shared by the
+/// loaders and the streams.
+pub(crate) struct ChunkReader {
+    /// Block all chunks are read at, must not be a tag that moves during the load
+    pub(crate) block_number_or_tag: BlockNumberOrTag,
+    pub(crate) chunk_size: usize,
+    pub(crate) mode: LoadMode,
+    /// Name of the read duration metric of a chunk
+    pub(crate) metric: &'static str,
+}
+
+impl ChunkReader {
+    /// Read the item of each index and pass the items of a chunk with its failures and the end of its range to `on_chunk`,
+    /// until it returns false. An item of `None` is filtered. A broken item is recorded as failure of the address returned by
+    /// `address_of` in lenient mode, unless the error has the address of the broken contract.
+    pub(crate) fn read<P: StateProviderFactory, T>(
+        &self,
+        provider_factory: &P,
+        indexes: Range<usize>,
+        address_of: impl Fn(usize) -> Address,
+        mut read_item: impl FnMut(&StateProviderBox, usize) -> eyre::Result<Option<T>>,
+        mut on_chunk: impl FnMut(Vec<T>, Vec<LoadFailure>, usize) -> bool,
+    ) -> eyre::Result<()> {
+        let chunk_size = self.chunk_size.max(1);
+        for start in indexes.clone().step_by(chunk_size) {
+            let end = std::cmp::min(start + chunk_size, indexes.end);
+            let chunk_started = Instant::now();
+            // To avoid long-running transactions we create a new provider for each chunk at the same block.
+            let provider = state_provider(provider_factory, &self.block_number_or_tag)?;
+            let mut items = Vec::with_capacity(end - start);
+            let mut failures = vec![];
+            for idx in start..end {
+                if let Some(item) = self.mode.check(read_item(&provider, idx), address_of(idx), idx, &mut failures)?.flatten() {
+                    items.push(item);
+                }
+            }
+            record_read_duration(self.metric, chunk_started.elapsed());
+            if !on_chunk(items, failures, end) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
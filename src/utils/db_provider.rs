@@ -1,12 +1,13 @@
 use crate::utils::wrapped_provider::WrappedProviderFactory;
-use alloy::eips::BlockNumberOrTag;
+use crate::utils::DexSyncError;
+use alloy::eips::{BlockNumHash, BlockNumberOrTag};
 use reth_chainspec::ChainSpecBuilder;
 use reth_db::mdbx::DatabaseArguments;
 use reth_db::{open_db_read_only, ClientVersion, DatabaseEnv};
 use reth_node_ethereum::EthereumNode;
 use reth_node_types::NodeTypesWithDBAdapter;
 use reth_provider::providers::StaticFileProvider;
-use reth_provider::{ProviderError, ProviderFactory, ProviderResult, StateProviderBox, StateProviderFactory};
+use reth_provider::{ProviderError, ProviderFactory, StateProviderBox, StateProviderFactory};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -35,10 +36,25 @@ pub fn init_db_read_only(db_path: &Path) -> eyre::Result<WrappedProviderFactory>
 pub fn state_provider<P: StateProviderFactory>(
     provider_factory: &P,
     block_number_or_tag: &BlockNumberOrTag,
-) -> ProviderResult<StateProviderBox> {
+) -> Result<StateProviderBox, DexSyncError> {
     match block_number_or_tag {
-        BlockNumberOrTag::Number(block_number) => provider_factory.history_by_block_number(*block_number),
-        BlockNumberOrTag::Latest => provider_factory.latest(),
-        _ => Err(ProviderError::UnsupportedProvider),
+        BlockNumberOrTag::Number(block_number) => Ok(provider_factory.history_by_block_number(*block_number)?),
+        BlockNumberOrTag::Latest => Ok(provider_factory.latest()?),
+        block_tag => Err(DexSyncError::UnsupportedBlockTag(*block_tag)),
     }
 }
+
+/// Resolve the block number or tag to a concrete block. Loaders resolve `Latest` once and read all chunks at that block, so the
+/// result is a consistent snapshot even if the node advances during the load.
+pub fn resolve_block<P: StateProviderFactory>(
+    provider_factory: &P,
+    block_number_or_tag: &BlockNumberOrTag,
+) -> Result<BlockNumHash, DexSyncError> {
+    let block_number = match block_number_or_tag {
+        BlockNumberOrTag::Number(block_number) => *block_number,
+        BlockNumberOrTag::Latest => provider_factory.best_block_number()?,
+        block_tag => return Err(DexSyncError::UnsupportedBlockTag(*block_tag)),
+    };
+    let block_hash = provider_factory.block_hash(block_number)?.ok_or(ProviderError::HeaderNotFound(block_number.into()))?;
+    Ok(BlockNumHash::new(block_number, block_hash))
+}
@@ -0,0 +1,43 @@
+use crate::utils::CacheError;
+use alloy::eips::BlockNumberOrTag;
+use alloy_primitives::{Address, B256};
+use reth_db::DatabaseError;
+use reth_provider::ProviderError;
+use thiserror::Error;
+
+/// Errors of the readers. They are returned inside `eyre::Report`, use `downcast_ref::<DexSyncError>()` to match on them.
+#[derive(Debug, Error)]
+pub enum DexSyncError {
+    #[error("Contract not found: {0:#?}")]
+    MissingContract(Address),
+    #[error("Storage slot {slot} not found: {address:#?}")]
+    MissingSlot { address: Address, slot: B256 },
+    #[error("Invalid storage layout of {address:#?}: {reason}")]
+    InvalidLayout { address: Address, reason: String },
+    #[error("Invalid proof of {address:#?}: {reason}")]
+    InvalidProof { address: Address, reason: String },
+    #[error("Invalid pool {address:#?}: {reason}")]
+    InvalidPool { address: Address, reason: String },
+    #[error("Invalid token pair {token_a:#?}/{token_b:#?}: {reason}")]
+    InvalidTokenPair { token_a: Address, token_b: Address, reason: String },
+    #[error("Unsupported block tag: {0}")]
+    UnsupportedBlockTag(BlockNumberOrTag),
+    #[error("Unsupported filter: {0}")]
+    UnsupportedFilter(&'static str),
+    #[error("Provider error: {0}")]
+    Provider(#[from] ProviderError),
+    #[error("Cache error: {0}")]
+    Cache(#[from] CacheError),
+}
+
+impl DexSyncError {
+    pub fn invalid_layout(address: Address, reason: impl Into<String>) -> Self {
+        DexSyncError::InvalidLayout { address, reason: reason.into() }
+    }
+}
+
+impl From<DatabaseError> for DexSyncError {
+    fn from(error: DatabaseError) -> Self {
+        DexSyncError::Provider(error.into())
+    }
+}
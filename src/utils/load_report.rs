@@ -0,0 +1,63 @@
+use crate::utils::DexSyncError;
+use alloy_primitives::Address;
+
+/// How loaders handle a pool that cannot be read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Abort the load with the error of the first broken pool
+    #[default]
+    Strict,
+    /// Skip broken pools and report them as failures. Provider and cache errors still abort the load.
+    Lenient,
+}
+
+/// A pool skipped by a lenient load.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadFailure {
+    /// Address of the contract with the broken storage, the factory if the pool address could not be read
+    pub address: Address,
+    /// Pair index of the factory or pool id of the position manager
+    pub index: usize,
+    pub reason: String,
+}
+
+impl LoadFailure {
+    fn new(error: &eyre::Report, address: Address, index: usize) -> Self {
+        let address = match error.downcast_ref::<DexSyncError>() {
+            Some(DexSyncError::MissingContract(address))
+            | Some(DexSyncError::MissingSlot { address, .. })
+            | Some(DexSyncError::InvalidLayout { address, .. })
+            | Some(DexSyncError::InvalidProof { address, .. })
+            | Some(DexSyncError::InvalidPool { address, .. }) => *address,
+            _ => address,
+        };
+        Self { address, index, reason: error.to_string() }
+    }
+}
+
+impl LoadMode {
+    /// Return the value, or in lenient mode record a pool error as failure and return `None`.
+    pub(crate) fn check<T>(
+        &self,
+        result: eyre::Result<T>,
+        address: Address,
+        index: usize,
+        failures: &mut Vec<LoadFailure>,
+    ) -> eyre::Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if *self == LoadMode::Lenient && !is_fatal(&e) => {
+                failures.push(LoadFailure::new(&e, address, index));
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn is_fatal(error: &eyre::Report) -> bool {
+    matches!(
+        error.downcast_ref::<DexSyncError>(),
+        Some(DexSyncError::Provider(_)) | Some(DexSyncError::Cache(_)) | Some(DexSyncError::UnsupportedBlockTag(_))
+    )
+}
@@ -1,8 +1,36 @@
 mod cache;
+pub(crate) mod chunk_reader;
 mod db_provider;
+mod error;
+mod load_report;
+mod pool_activity;
+mod pool_authenticity;
+mod pool_stream;
+pub(crate) mod progress;
 mod storage_access_helper;
+mod storage_history;
+mod storage_layout;
+mod storage_proof;
+pub mod telemetry;
 mod wrapped_provider;
 
 pub use cache::{CacheError, DexSyncCache};
-pub use db_provider::{init_db_read_only, init_db_read_only_from_env, state_provider};
-pub use storage_access_helper::{read_all_storage_entries, read_array_item};
+pub use db_provider::{init_db_read_only, init_db_read_only_from_env, resolve_block, state_provider};
+pub use error::DexSyncError;
+pub use load_report::{LoadFailure, LoadMode};
+pub use pool_activity::{read_activity, PoolActivity};
+pub use pool_authenticity::{read_code_hash, PoolDeployment};
+pub use pool_stream::{ChunkSender, LoadChunk, PoolStream};
+pub use progress::{LoadPhase, LoadProgress, NoopProgress, ProgressObserver};
+pub use storage_access_helper::{
+    array_item_slot, missing_slot, read_all_storage_entries, read_array_item, read_required_storage, read_storage,
+};
+pub use storage_history::{read_storage_history, StorageChange, StorageHistory};
+pub use storage_layout::{slot, slot_offset, DynArray, Field, Mapping, PackedValue, StorageLocation};
+pub use storage_proof::{read_storage_proof, verify_storage_proof};
+
+/// Paths used by the exported macros, downstream crates need no direct dependency on them.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloy_primitives::B256;
+}
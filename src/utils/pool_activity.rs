@@ -0,0 +1,128 @@
+use alloy_primitives::{Address, BlockNumber, B256};
+use reth_db::cursor::{DbCursorRO, DbDupCursorRO};
+use reth_db::models::sharded_key::ShardedKey;
+use reth_db::models::storage_sharded_key::StorageShardedKey;
+use reth_db::transaction::DbTx;
+use reth_db::{tables, Database, DatabaseError};
+use reth_prune_types::PruneSegment;
+
+/// Creation and last activity of a pool from the history indices. The creation is `None` if it was pruned, the last activity
+/// if all changes of the slot were pruned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolActivity {
+    /// First block the account of the pool changed
+    pub created_block: Option<BlockNumber>,
+    /// Last block the state slot (reserves or slot0) of the pool changed
+    pub last_active_block: Option<BlockNumber>,
+}
+
+impl PoolActivity {
+    /// Whether the pool did not change since the block, e.g. the block 90 days ago. A pool without any change is inactive.
+    pub fn inactive_since(&self, block_number: BlockNumber) -> bool {
+        !matches!(self.last_active_block, Some(last_active_block) if last_active_block >= block_number)
+    }
+}
+
+/// Read the creation block of the account and the last block the slot changed from the `AccountsHistory` and
+/// `StoragesHistory` indices. Works without receipts. With a pruned account history the first retained change is only the
+/// creation if the account did not exist before it according to its changeset.
+pub fn read_activity<DB: Database>(db: &DB, address: Address, slot: B256) -> eyre::Result<PoolActivity> {
+    let tx = db.tx()?;
+
+    // the shards are ordered by their highest block, the first shard holds the first change
+    let mut account_cursor = tx.cursor_read::<tables::AccountsHistory>()?;
+    let created_block = match account_cursor.seek(ShardedKey::new(address, 0))? {
+        Some((sharded_key, block_numbers)) if sharded_key.key == address => block_numbers.iter().next(),
+        _ => None,
+    };
+    let created_block = match (created_block, pruned_block(&tx, PruneSegment::AccountHistory)?) {
+        (Some(block_number), Some(_)) => {
+            let mut changeset_cursor = tx.cursor_dup_read::<tables::AccountChangeSets>()?;
+            match changeset_cursor.seek_by_key_subkey(block_number, address)? {
+                Some(before) if before.address == address && before.info.is_none() => Some(block_number),
+                _ => None,
+            }
+        }
+        (created_block, _) => created_block,
+    };
+
+    // the last shard is always keyed with `u64::MAX`
+    let mut storage_cursor = tx.cursor_read::<tables::StoragesHistory>()?;
+    let last_active_block = storage_cursor
+        .seek_exact(StorageShardedKey::new(address, slot, u64::MAX))?
+        .and_then(|(_, block_numbers)| block_numbers.iter().last());
+
+    Ok(PoolActivity { created_block, last_active_block })
+}
+
+/// Highest pruned block of the segment, `None` if nothing was pruned.
+fn pruned_block<TX: DbTx>(tx: &TX, segment: PruneSegment) -> Result<Option<BlockNumber>, DatabaseError> {
+    Ok(tx.get::<tables::PruneCheckpoints>(segment)?.and_then(|checkpoint| checkpoint.block_number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use reth_db::models::AccountBeforeTx;
+    use reth_db::transaction::DbTxMut;
+    use reth_db::BlockNumberList;
+    use reth_primitives::Account;
+    use reth_prune_types::{PruneCheckpoint, PruneMode};
+    use reth_stages::test_utils::TestStageDB;
+
+    #[test]
+    fn test_read_pool_activity() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pool_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let slot = B256::with_last_byte(8);
+        test_db.commit(|tx| {
+            tx.put::<tables::AccountsHistory>(ShardedKey::new(pool_address, 20), BlockNumberList::new_pre_sorted([10, 20]))?;
+            tx.put::<tables::AccountsHistory>(ShardedKey::new(pool_address, u64::MAX), BlockNumberList::new_pre_sorted([30]))?;
+            tx.put::<tables::StoragesHistory>(StorageShardedKey::new(pool_address, slot, 20), BlockNumberList::new_pre_sorted([11, 20]))?;
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pool_address, slot, u64::MAX),
+                BlockNumberList::new_pre_sorted([25, 30]),
+            )?;
+            Ok(())
+        })?;
+
+        let activity = read_activity(test_db.factory.db_ref(), pool_address, slot)?;
+        assert_eq!(activity, PoolActivity { created_block: Some(10), last_active_block: Some(30) });
+        assert!(!activity.inactive_since(30));
+        assert!(activity.inactive_since(31));
+
+        // unknown pool
+        let activity = read_activity(test_db.factory.db_ref(), Address::with_last_byte(1), slot)?;
+        assert_eq!(activity, PoolActivity::default());
+        assert!(activity.inactive_since(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_pool_activity_pruned() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        // the first retained change of the old pool is an update, the new pool is created after the pruned blocks
+        let (old_pool, new_pool) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let slot = B256::with_last_byte(8);
+        test_db.commit(|tx| {
+            tx.put::<tables::PruneCheckpoints>(
+                PruneSegment::AccountHistory,
+                PruneCheckpoint { block_number: Some(15), tx_number: None, prune_mode: PruneMode::Before(16) },
+            )?;
+            tx.put::<tables::AccountsHistory>(ShardedKey::new(old_pool, u64::MAX), BlockNumberList::new_pre_sorted([20, 30]))?;
+            tx.put::<tables::AccountsHistory>(ShardedKey::new(new_pool, u64::MAX), BlockNumberList::new_pre_sorted([25]))?;
+            tx.put::<tables::AccountChangeSets>(20, AccountBeforeTx { address: old_pool, info: Some(Account::default()) })?;
+            tx.put::<tables::AccountChangeSets>(25, AccountBeforeTx { address: new_pool, info: None })?;
+            Ok(())
+        })?;
+
+        assert_eq!(read_activity(test_db.factory.db_ref(), old_pool, slot)?.created_block, None);
+        assert_eq!(read_activity(test_db.factory.db_ref(), new_pool, slot)?.created_block, Some(25));
+
+        Ok(())
+    }
+}
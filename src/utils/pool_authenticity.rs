@@ -0,0 +1,134 @@
+use crate::univ2::{PAIR_INIT_CODE_HASH, UNI_V2_FACTORY};
+use crate::univ3::{POOL_INIT_CODE_HASH, UNI_V3_FACTORY};
+use crate::utils::DexSyncError;
+use alloy_primitives::{address, Address, B256};
+use reth_provider::StateProvider;
+
+/// Expected CREATE2 deployment of the pools of a factory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolDeployment {
+    pub factory: Address,
+    pub init_code_hash: B256,
+    /// Hash of the runtime bytecode shared by all pools, `None` if the code embeds immutables of the pool like Uniswap V3
+    pub runtime_code_hash: Option<B256>,
+}
+
+/// Uniswap V2 USDC/WETH pair, its runtime code is the one of all pairs of the factory
+const UNI_V2_REFERENCE_PAIR: Address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+
+impl PoolDeployment {
+    /// Deployment without a runtime code hash, only the CREATE2 address is checked. See `with_runtime_code_hash`.
+    pub fn new(factory: Address, init_code_hash: B256) -> Self {
+        Self { factory, init_code_hash, runtime_code_hash: None }
+    }
+
+    /// Uniswap V2 pairs, the runtime code hash is read from the USDC/WETH pair at the state of the provider.
+    pub fn uniswap_v2<T: StateProvider>(provider: &T) -> Result<Self, DexSyncError> {
+        Ok(Self::new(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH).with_runtime_code_hash(read_code_hash(provider, UNI_V2_REFERENCE_PAIR)?))
+    }
+
+    /// Uniswap V3 pools. The code hash is not checked, the runtime code embeds the immutables factory, tokens, fee and tick
+    /// spacing of each pool.
+    pub fn uniswap_v3() -> Self {
+        Self::new(UNI_V3_FACTORY, POOL_INIT_CODE_HASH)
+    }
+
+    pub fn with_runtime_code_hash(mut self, runtime_code_hash: B256) -> Self {
+        self.runtime_code_hash = Some(runtime_code_hash);
+        self
+    }
+
+    /// Address of the pool deployed by the factory with the salt.
+    pub fn pool_address(&self, salt: B256) -> Address {
+        self.factory.create2(salt, self.init_code_hash)
+    }
+
+    /// Verify the pool is deployed by the factory with the salt and runs the expected code.
+    pub fn verify<T: StateProvider>(&self, provider: T, address: Address, salt: B256) -> Result<(), DexSyncError> {
+        let expected = self.pool_address(salt);
+        if address != expected {
+            return Err(DexSyncError::InvalidPool {
+                address,
+                reason: format!("not deployed by {:#?}, expected {:#?}", self.factory, expected),
+            });
+        }
+        let code_hash = read_code_hash(&provider, address)?;
+        if let Some(runtime_code_hash) = self.runtime_code_hash {
+            if code_hash != runtime_code_hash {
+                return Err(DexSyncError::InvalidPool { address, reason: format!("code hash {} != {}", code_hash, runtime_code_hash) });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the hash of the runtime bytecode of a contract, e.g. of a known pool to verify the others against.
+pub fn read_code_hash<T: StateProvider>(provider: &T, address: Address) -> Result<B256, DexSyncError> {
+    match provider.basic_account(address)?.and_then(|account| account.bytecode_hash) {
+        Some(code_hash) => Ok(code_hash),
+        None => Err(DexSyncError::MissingContract(address)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+    use alloy_sol_types::SolValue;
+    use reth_primitives::Account;
+    use reth_stages::test_utils::TestStageDB;
+
+    #[test]
+    fn test_verify_pool() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let code_hash = B256::with_last_byte(1);
+        test_db.insert_accounts_and_storages(vec![
+            (pair_address, (Account { bytecode_hash: Some(code_hash), ..Default::default() }, vec![])),
+            (Address::with_last_byte(1), (Account { bytecode_hash: Some(code_hash), ..Default::default() }, vec![])),
+        ])?;
+
+        let salt = keccak256((usdc, weth).abi_encode_packed());
+        let deployment = PoolDeployment::new(UNI_V2_FACTORY, PAIR_INIT_CODE_HASH);
+        deployment.verify(test_db.factory.latest()?, pair_address, salt)?;
+        deployment.clone().with_runtime_code_hash(code_hash).verify(test_db.factory.latest()?, pair_address, salt)?;
+
+        // same code at another address
+        let err = deployment.verify(test_db.factory.latest()?, Address::with_last_byte(1), salt).unwrap_err();
+        assert!(matches!(err, DexSyncError::InvalidPool { address, .. } if address == Address::with_last_byte(1)));
+
+        let err =
+            deployment.with_runtime_code_hash(B256::with_last_byte(2)).verify(test_db.factory.latest()?, pair_address, salt).unwrap_err();
+        assert!(matches!(err, DexSyncError::InvalidPool { address, .. } if address == pair_address));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_uniswap_v2_deployment() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        // a pair of the factory running other code
+        let other_pair = address!("a478c2975ab1ea89e8196811f51a7b7ade33eb11");
+        test_db.insert_accounts_and_storages(vec![
+            (pair_address, (Account { bytecode_hash: Some(B256::with_last_byte(1)), ..Default::default() }, vec![])),
+            (other_pair, (Account { bytecode_hash: Some(B256::with_last_byte(2)), ..Default::default() }, vec![])),
+        ])?;
+
+        let deployment = PoolDeployment::uniswap_v2(&test_db.factory.latest()?)?;
+        assert_eq!(deployment.runtime_code_hash, Some(B256::with_last_byte(1)));
+        let dai = address!("6b175474e89094c44da98b954eedeac495271d0f");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let err = deployment.verify(test_db.factory.latest()?, other_pair, keccak256((dai, weth).abi_encode_packed())).unwrap_err();
+        assert!(matches!(err, DexSyncError::InvalidPool { reason, .. } if reason.starts_with("code hash")));
+
+        // without the reference pair
+        let err = PoolDeployment::uniswap_v2(&TestStageDB::default().factory.latest()?).unwrap_err();
+        assert!(matches!(err, DexSyncError::MissingContract(address) if address == pair_address));
+        assert_eq!(PoolDeployment::uniswap_v3().runtime_code_hash, None);
+
+        Ok(())
+    }
+}
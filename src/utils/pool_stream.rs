@@ -0,0 +1,132 @@
+use crate::utils::LoadFailure;
+use alloy::eips::BlockNumHash;
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Number of chunks buffered before the blocking reader waits for the consumer.
+const STREAM_BUFFER: usize = 4;
+
+/// Items decoded from one chunk together with the progress of the whole load.
+#[derive(Debug)]
+pub struct LoadChunk<T> {
+    pub items: Vec<T>,
+    /// Pools of the chunk skipped by a lenient load
+    pub failures: Vec<LoadFailure>,
+    /// Number of pools processed so far, including filtered ones
+    pub done: usize,
+    pub total: usize,
+    /// Block of the snapshot, the same for all chunks of a stream
+    pub block: BlockNumHash,
+}
+
+/// Stream of chunks read on the blocking thread pool. Dropping the stream cancels the load after the current chunk.
+pub struct PoolStream<T> {
+    receiver: mpsc::Receiver<eyre::Result<LoadChunk<T>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Producer side of a `PoolStream`.
+pub struct ChunkSender<T> {
+    sender: mpsc::Sender<eyre::Result<LoadChunk<T>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T> ChunkSender<T> {
+    /// Send a chunk to the stream. Returns false if the stream was cancelled and the producer should stop.
+    pub fn send(&self, chunk: LoadChunk<T>) -> bool {
+        !self.is_cancelled() && self.sender.blocking_send(Ok(chunk)).is_ok()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.sender.is_closed()
+    }
+}
+
+impl<T: Send + 'static> PoolStream<T> {
+    /// Run the producer with `spawn_blocking`. An error of the producer is the last item of the stream. Must be called within a tokio runtime.
+    pub fn spawn<F>(producer: F) -> Self
+    where
+        F: FnOnce(&ChunkSender<T>) -> eyre::Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let chunk_sender = ChunkSender { sender, cancelled: cancelled.clone() };
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = producer(&chunk_sender) {
+                let _ = chunk_sender.sender.blocking_send(Err(e));
+            }
+        });
+        Self { receiver, cancelled }
+    }
+}
+
+impl<T> PoolStream<T> {
+    /// Stop the load after the current chunk. Chunks already read are still yielded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Stream for PoolStream<T> {
+    type Item = eyre::Result<LoadChunk<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for PoolStream<T> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::eyre;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_pool_stream() {
+        let stream = PoolStream::spawn(|sender| {
+            for done in [2, 4] {
+                sender.send(LoadChunk { items: vec![done - 1, done], failures: vec![], done, total: 5, block: BlockNumHash::default() });
+            }
+            Err(eyre!("LAST_CHUNK_FAILED"))
+        });
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[1].as_ref().unwrap().items, vec![3, 4]);
+        assert_eq!(chunks[1].as_ref().unwrap().done, 4);
+        assert!(chunks[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pool_stream_cancel() {
+        let (done_sender, done_receiver) = tokio::sync::oneshot::channel();
+        let mut stream = PoolStream::spawn(move |sender| {
+            let mut sent = 0;
+            while sender.send(LoadChunk {
+                items: vec![sent],
+                failures: vec![],
+                done: sent,
+                total: usize::MAX,
+                block: BlockNumHash::default(),
+            }) {
+                sent += 1;
+            }
+            let _ = done_sender.send(sent);
+            Ok(())
+        });
+        assert_eq!(stream.next().await.unwrap().unwrap().items, vec![0]);
+        stream.cancel();
+        drop(stream);
+        // the producer stops instead of reading all chunks
+        assert!(done_receiver.await.unwrap() < 100);
+    }
+}
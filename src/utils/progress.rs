@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+/// Pass of a load, each pass reports its progress from zero to its total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadPhase {
+    /// Reading the pools from the factory or position manager
+    Pools,
+    /// Reading the reserves of the loaded pairs
+    Reserves,
+}
+
+/// Progress of a long-running load.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub phase: LoadPhase,
+    pub done: usize,
+    pub total: usize,
+    /// Index of the last pool read, the pair index of the factory or the pool id of the position manager. The position in the
+    /// loaded pairs for the reserves.
+    pub factory_index: usize,
+    pub elapsed: Duration,
+}
+
+/// Observer notified after each chunk of a loader.
+pub trait ProgressObserver: Send + Sync {
+    fn on_progress(&self, progress: &LoadProgress);
+}
+
+/// Observer ignoring all progress.
+#[derive(Debug, Default)]
+pub struct NoopProgress;
+
+impl ProgressObserver for NoopProgress {
+    fn on_progress(&self, _progress: &LoadProgress) {}
+}
+
+impl<F: Fn(&LoadProgress) + Send + Sync> ProgressObserver for F {
+    fn on_progress(&self, progress: &LoadProgress) {
+        self(progress)
+    }
+}
+
+/// Tracks the start of a load and notifies the observer.
+pub(crate) struct ProgressTracker<'a> {
+    observer: &'a dyn ProgressObserver,
+    phase: LoadPhase,
+    started: Instant,
+    total: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn new(observer: &'a dyn ProgressObserver, phase: LoadPhase, total: usize) -> Self {
+        Self { observer, phase, started: Instant::now(), total }
+    }
+
+    pub(crate) fn update(&self, done: usize, factory_index: usize) {
+        self.observer.on_progress(&LoadProgress {
+            phase: self.phase,
+            done,
+            total: self.total,
+            factory_index,
+            elapsed: self.started.elapsed(),
+        });
+    }
+}
@@ -1,10 +1,39 @@
+use crate::utils::telemetry::record_storage_reads;
+use crate::utils::DexSyncError;
 use alloy_primitives::{Address, StorageValue, B256, U256};
-use eyre::eyre;
 use reth_db::cursor::DbCursorRO;
-use reth_db::{tables, Database, DatabaseEnv};
+use reth_db::transaction::DbTx;
+use reth_db::{tables, Database};
 use reth_primitives::StorageEntry;
 use reth_provider::StateProvider;
-use std::sync::Arc;
+
+/// Read a storage slot
+pub fn read_storage<T: StateProvider>(provider: &T, contract_address: Address, slot: B256) -> Result<Option<StorageValue>, DexSyncError> {
+    record_storage_reads(1);
+    Ok(provider.storage(contract_address, slot)?)
+}
+
+/// Read a storage slot that must be set. Fails with `MissingContract` if the account does not exist, otherwise with `MissingSlot`.
+pub fn read_required_storage<T: StateProvider>(provider: &T, contract_address: Address, slot: B256) -> Result<StorageValue, DexSyncError> {
+    match read_storage(provider, contract_address, slot)? {
+        Some(storage_value) => Ok(storage_value),
+        None => Err(missing_slot(provider, contract_address, slot)),
+    }
+}
+
+/// The error for an unset slot, `MissingContract` if the account does not exist.
+pub fn missing_slot<T: StateProvider>(provider: &T, contract_address: Address, slot: B256) -> DexSyncError {
+    match provider.basic_account(contract_address) {
+        Ok(None) => DexSyncError::MissingContract(contract_address),
+        Ok(Some(_)) => DexSyncError::MissingSlot { address: contract_address, slot },
+        Err(e) => DexSyncError::Provider(e),
+    }
+}
+
+/// Storage key of an array item
+pub fn array_item_slot(slot: B256, idx: usize) -> B256 {
+    B256::from(U256::from_be_slice(slot.as_slice()) + U256::from(idx))
+}
 
 /// Read an array item from storage
 pub fn read_array_item<T: StateProvider>(
@@ -12,22 +41,14 @@ pub fn read_array_item<T: StateProvider>(
     contract_address: Address,
     slot: B256,
     idx: usize,
-) -> eyre::Result<Option<StorageValue>> {
-    let storage_key = B256::from(U256::from_be_slice(slot.as_slice()) + U256::from(idx));
-
-    match provider.storage(contract_address, storage_key) {
-        Ok(storage_value) => match storage_value {
-            None => Ok(None),
-            Some(storage_value) => Ok(Some(storage_value)),
-        },
-        Err(e) => Err(eyre!(e)),
-    }
+) -> Result<Option<StorageValue>, DexSyncError> {
+    read_storage(provider, contract_address, array_item_slot(slot, idx))
 }
 
 /// Read all storage entries for a given address
-pub fn read_all_storage_entries(db_ref: Arc<DatabaseEnv>, address: Address) -> eyre::Result<Vec<StorageEntry>> {
-    let tx = db_ref.tx()?;
-    let mut cursor = tx.new_cursor::<tables::PlainStorageState>()?;
+pub fn read_all_storage_entries<DB: Database>(db: &DB, address: Address) -> Result<Vec<StorageEntry>, DexSyncError> {
+    let tx = db.tx()?;
+    let mut cursor = tx.cursor_read::<tables::PlainStorageState>()?;
     let walker = cursor.walk(Some(address))?;
 
     let mut slots = vec![];
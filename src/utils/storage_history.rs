@@ -0,0 +1,147 @@
+use crate::utils::DexSyncError;
+use alloy_primitives::{Address, BlockNumber, StorageValue, B256};
+use reth_db::cursor::{DbCursorRO, DbDupCursorRO};
+use reth_db::models::storage_sharded_key::StorageShardedKey;
+use reth_db::models::BlockNumberAddress;
+use reth_db::transaction::DbTx;
+use reth_db::{tables, Database};
+use reth_provider::ProviderError;
+use std::ops::RangeInclusive;
+
+/// A change of a storage slot. The value is the one after the block has been executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageChange {
+    pub block_number: BlockNumber,
+    pub value: StorageValue,
+}
+
+/// All changes of a storage slot in a block range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageHistory {
+    /// Value before the first block of the range
+    pub initial_value: StorageValue,
+    pub changes: Vec<StorageChange>,
+}
+
+/// Read all changes of a storage slot in the block range from the `StoragesHistory` index and the `StorageChangeSets`.
+pub fn read_storage_history<DB: Database>(
+    db: &DB,
+    address: Address,
+    slot: B256,
+    block_range: RangeInclusive<BlockNumber>,
+) -> eyre::Result<StorageHistory> {
+    let tx = db.tx()?;
+    let mut history_cursor = tx.cursor_read::<tables::StoragesHistory>()?;
+    let mut changeset_cursor = tx.cursor_dup_read::<tables::StorageChangeSets>()?;
+    let mut plain_state_cursor = tx.cursor_dup_read::<tables::PlainStorageState>()?;
+
+    // Collect all blocks with a change in the range and the first change after the range.
+    let mut change_blocks = vec![];
+    let mut next_change_block = None;
+    let walker = history_cursor.walk(Some(StorageShardedKey::new(address, slot, *block_range.start())))?;
+    'shards: for table_row_result in walker {
+        let (sharded_key, block_numbers) = table_row_result?;
+        if sharded_key.address != address || sharded_key.sharded_key.key != slot {
+            break;
+        }
+        for block_number in block_numbers.iter() {
+            if block_number < *block_range.start() {
+                continue;
+            }
+            if block_number > *block_range.end() {
+                next_change_block = Some(block_number);
+                break 'shards;
+            }
+            change_blocks.push(block_number);
+        }
+    }
+
+    // A changeset stores the value before the change. The value after a change is the value before the next change or the
+    // current value if there is no later change.
+    let mut values_before = Vec::with_capacity(change_blocks.len() + 1);
+    for block_number in change_blocks.iter().copied().chain(next_change_block) {
+        let value = match changeset_cursor.seek_by_key_subkey(BlockNumberAddress((block_number, address)), slot)? {
+            Some(entry) if entry.key == slot => entry.value,
+            _ => {
+                return Err(DexSyncError::Provider(ProviderError::StorageChangesetNotFound {
+                    block_number,
+                    address,
+                    storage_key: Box::new(slot),
+                })
+                .into())
+            }
+        };
+        values_before.push(value);
+    }
+    let current_value = match plain_state_cursor.seek_by_key_subkey(address, slot)? {
+        Some(entry) if entry.key == slot => entry.value,
+        _ => StorageValue::ZERO,
+    };
+
+    let initial_value = values_before.first().copied().unwrap_or(current_value);
+    let changes = change_blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, block_number)| StorageChange {
+            block_number: *block_number,
+            value: values_before.get(idx + 1).copied().unwrap_or(current_value),
+        })
+        .collect();
+
+    Ok(StorageHistory { initial_value, changes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, U256};
+    use reth_db::transaction::DbTxMut;
+    use reth_db::BlockNumberList;
+    use reth_primitives::{Account, StorageEntry};
+    use reth_stages::test_utils::TestStageDB;
+
+    #[test]
+    fn test_read_storage_history() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let slot = B256::with_last_byte(8);
+
+        // value is changed in block 10 (1 -> 2), 20 (2 -> 3) and 30 (3 -> 4)
+        test_db.insert_accounts_and_storages(vec![(pair_address, (Account::default(), vec![StorageEntry::new(slot, U256::from(4))]))])?;
+        test_db.commit(|tx| {
+            tx.put::<tables::StoragesHistory>(
+                StorageShardedKey::new(pair_address, slot, u64::MAX),
+                BlockNumberList::new_pre_sorted([10, 20, 30]),
+            )?;
+            for (block_number, value_before) in [(10, 1), (20, 2), (30, 3)] {
+                tx.put::<tables::StorageChangeSets>(
+                    BlockNumberAddress((block_number, pair_address)),
+                    StorageEntry::new(slot, U256::from(value_before)),
+                )?;
+            }
+            Ok(())
+        })?;
+
+        let history = read_storage_history(test_db.factory.db_ref(), pair_address, slot, 0..=100)?;
+        assert_eq!(history.initial_value, U256::from(1));
+        assert_eq!(
+            history.changes,
+            vec![
+                StorageChange { block_number: 10, value: U256::from(2) },
+                StorageChange { block_number: 20, value: U256::from(3) },
+                StorageChange { block_number: 30, value: U256::from(4) },
+            ]
+        );
+
+        let history = read_storage_history(test_db.factory.db_ref(), pair_address, slot, 15..=25)?;
+        assert_eq!(history.initial_value, U256::from(2));
+        assert_eq!(history.changes, vec![StorageChange { block_number: 20, value: U256::from(3) }]);
+
+        let history = read_storage_history(test_db.factory.db_ref(), pair_address, slot, 31..=40)?;
+        assert_eq!(history.initial_value, U256::from(4));
+        assert!(history.changes.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Typed Solidity storage layouts. Locations compute the storage keys like solc and fields decode packed values, e.g. the
+//! `ticks` mapping of a pool is `Mapping::<I24, TickInfoSlots>::new(slot(5))` with `TickInfoSlots` declared by `storage_struct!`
+//! in `univ3_pool`.
+
+use crate::utils::{read_required_storage, read_storage, DexSyncError};
+use alloy_primitives::{keccak256, Address, Signed, StorageValue, Uint, B256, U256};
+use alloy_sol_types::SolValue;
+use reth_provider::StateProvider;
+use std::marker::PhantomData;
+
+/// Storage key of a slot number.
+pub const fn slot(number: u64) -> B256 {
+    let bytes = number.to_be_bytes();
+    let mut slot = [0u8; 32];
+    let mut idx = 0;
+    while idx < 8 {
+        slot[24 + idx] = bytes[idx];
+        idx += 1;
+    }
+    B256::new(slot)
+}
+
+/// Storage key `offset` slots after the slot.
+pub fn slot_offset(slot: B256, offset: u64) -> B256 {
+    B256::from(U256::from_be_slice(slot.as_slice()).wrapping_add(U256::from(offset)))
+}
+
+/// A typed location in storage starting at a slot.
+pub trait StorageLocation {
+    /// Number of slots the type occupies, the stride of an array
+    const SLOTS: u64;
+
+    fn at_slot(slot: B256) -> Self;
+}
+
+/// A value packed into a storage slot.
+pub trait PackedValue: Sized {
+    /// Size in bytes
+    const SIZE: usize;
+
+    /// Decode from the big endian bytes of the value
+    fn from_be_slice(bytes: &[u8]) -> Self;
+}
+
+impl<const BITS: usize, const LIMBS: usize> PackedValue for Uint<BITS, LIMBS> {
+    const SIZE: usize = BITS / 8;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        Uint::from_be_slice(bytes)
+    }
+}
+
+impl<const BITS: usize, const LIMBS: usize> PackedValue for Signed<BITS, LIMBS> {
+    const SIZE: usize = BITS / 8;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        // the size always matches the type
+        Signed::try_from_be_slice(bytes).unwrap()
+    }
+}
+
+macro_rules! impl_packed_value_for_primitive {
+    ($($ty:ty),*) => {$(
+        impl PackedValue for $ty {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+
+            fn from_be_slice(bytes: &[u8]) -> Self {
+                <$ty>::from_be_bytes(bytes.try_into().unwrap())
+            }
+        }
+    )*};
+}
+
+impl_packed_value_for_primitive!(u8, u16, u32, u64, u128);
+
+impl PackedValue for Address {
+    const SIZE: usize = 20;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        Address::from_slice(bytes)
+    }
+}
+
+impl PackedValue for B256 {
+    const SIZE: usize = 32;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        B256::from_slice(bytes)
+    }
+}
+
+impl PackedValue for bool {
+    const SIZE: usize = 1;
+
+    fn from_be_slice(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
+
+/// A value at a slot and byte offset like the `slot` and `offset` of `solc --storage-layout`. The offset counts from the
+/// lower-order end of the slot, the first member of a packed slot has offset 0.
+#[derive(Debug)]
+pub struct Field<T> {
+    pub slot: B256,
+    pub offset: usize,
+    _value: PhantomData<T>,
+}
+
+impl<T> Clone for Field<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Field<T> {}
+
+impl<T: PackedValue> Field<T> {
+    pub const fn new(slot: B256, offset: usize) -> Self {
+        Self { slot, offset, _value: PhantomData }
+    }
+
+    /// Decode the field from the value of its slot.
+    pub fn decode(&self, value: StorageValue) -> T {
+        let bytes: [u8; 32] = value.to_be_bytes();
+        let end = 32 - self.offset;
+        T::from_be_slice(&bytes[end - T::SIZE..end])
+    }
+
+    /// Read the field, an unset slot decodes to zero.
+    pub fn read<P: StateProvider>(&self, provider: &P, address: Address) -> Result<T, DexSyncError> {
+        Ok(self.decode(read_storage(provider, address, self.slot)?.unwrap_or_default()))
+    }
+
+    /// Read the field, fails if the slot is unset.
+    pub fn read_required<P: StateProvider>(&self, provider: &P, address: Address) -> Result<T, DexSyncError> {
+        Ok(self.decode(read_required_storage(provider, address, self.slot)?))
+    }
+}
+
+impl<T> StorageLocation for Field<T> {
+    const SLOTS: u64 = 1;
+
+    fn at_slot(slot: B256) -> Self {
+        Self { slot, offset: 0, _value: PhantomData }
+    }
+}
+
+/// `mapping(K => V)` at a slot. Only value type keys are supported, they are hashed with the 32 byte ABI encoding.
+#[derive(Debug)]
+pub struct Mapping<K, V> {
+    pub slot: B256,
+    _entry: PhantomData<(K, V)>,
+}
+
+impl<K, V> Clone for Mapping<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Mapping<K, V> {}
+
+impl<K: SolValue, V: StorageLocation> Mapping<K, V> {
+    pub const fn new(slot: B256) -> Self {
+        Self { slot, _entry: PhantomData }
+    }
+
+    /// Storage key of the entry `keccak256(abi.encode(key, slot))`.
+    pub fn entry_slot(&self, key: &K) -> B256 {
+        keccak256([key.abi_encode().as_slice(), self.slot.as_slice()].concat())
+    }
+
+    pub fn entry(&self, key: &K) -> V {
+        V::at_slot(self.entry_slot(key))
+    }
+}
+
+impl<K, V> StorageLocation for Mapping<K, V> {
+    const SLOTS: u64 = 1;
+
+    fn at_slot(slot: B256) -> Self {
+        Self { slot, _entry: PhantomData }
+    }
+}
+
+/// Dynamic array `T[]` at a slot. The slot holds the length, the items start at `keccak256(slot)`.
+#[derive(Debug)]
+pub struct DynArray<T> {
+    pub slot: B256,
+    _item: PhantomData<T>,
+}
+
+impl<T> Clone for DynArray<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DynArray<T> {}
+
+impl<T: StorageLocation> DynArray<T> {
+    pub const fn new(slot: B256) -> Self {
+        Self { slot, _item: PhantomData }
+    }
+
+    /// Storage key of the first slot of the item.
+    pub fn item_slot(&self, idx: usize) -> B256 {
+        slot_offset(keccak256(self.slot), idx as u64 * T::SLOTS)
+    }
+
+    pub fn item(&self, idx: usize) -> T {
+        T::at_slot(self.item_slot(idx))
+    }
+
+    pub fn read_len<P: StateProvider>(&self, provider: &P, address: Address) -> Result<usize, DexSyncError> {
+        Ok(read_storage(provider, address, self.slot)?.unwrap_or_default().to::<usize>())
+    }
+}
+
+impl<T> StorageLocation for DynArray<T> {
+    const SLOTS: u64 = 1;
+
+    fn at_slot(slot: B256) -> Self {
+        Self { slot, _item: PhantomData }
+    }
+}
+
+/// Declare the layout of a Solidity struct. Each member is given with its slot relative to the struct and its byte offset.
+///
+/// ```ignore
+/// storage_struct! {
+///     /// `PoolAddress.PoolKey`
+///     pub struct PoolKeySlots[2] {
+///         token0: Address => (0, 0),
+///         token1: Address => (1, 0),
+///         fee: U24 => (1, 20),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! storage_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident[$slots:expr] {
+            $($field:ident: $ty:ty => ($slot:expr, $offset:expr)),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $name {
+            pub slot: $crate::utils::__private::B256,
+        }
+
+        impl $crate::utils::StorageLocation for $name {
+            const SLOTS: u64 = $slots;
+
+            fn at_slot(slot: $crate::utils::__private::B256) -> Self {
+                Self { slot }
+            }
+        }
+
+        impl $name {
+            $(
+                pub fn $field(&self) -> $crate::utils::Field<$ty> {
+                    $crate::utils::Field::new($crate::utils::slot_offset(self.slot, $slot), $offset)
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::aliases::{I24, U24};
+    use alloy_primitives::{b256, U160};
+
+    storage_struct! {
+        struct PoolKeySlots[2] {
+            token0: Address => (0, 0),
+            token1: Address => (1, 0),
+            fee: U24 => (1, 20),
+        }
+    }
+
+    #[test]
+    fn test_storage_layout() {
+        assert_eq!(slot(5), b256!("0000000000000000000000000000000000000000000000000000000000000005"));
+
+        // ticks of a pool
+        let ticks = Mapping::<I24, Field<U256>>::new(slot(5));
+        let tick = I24::try_from(1740).unwrap();
+        assert_eq!(ticks.entry_slot(&tick), keccak256((tick, slot(5)).abi_encode()));
+        assert_eq!(ticks.entry(&tick).slot, b256!("87361ea236b1c1a4b101e72bd6c912613e5b68034f169d3f702e04d520b95e40"));
+
+        // nested mapping of an ERC20 allowance
+        let allowances = Mapping::<Address, Mapping<Address, Field<U256>>>::new(slot(1));
+        let (owner, spender) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let inner = keccak256((owner, slot(1)).abi_encode());
+        assert_eq!(allowances.entry(&owner).entry(&spender).slot, keccak256((spender, inner).abi_encode()));
+
+        // array of structs
+        let keys = DynArray::<PoolKeySlots>::new(slot(3));
+        assert_eq!(keys.item(2).slot, slot_offset(keccak256(slot(3)), 4));
+        assert_eq!(keys.item(2).fee().slot, slot_offset(keccak256(slot(3)), 5));
+    }
+
+    #[test]
+    fn test_packed_fields() {
+        let token1 = Address::with_last_byte(7);
+        let value = (U256::from(3000) << 160) | U256::from_be_slice(token1.as_slice());
+        let key = PoolKeySlots { slot: B256::ZERO };
+        assert_eq!(key.token1().decode(value), token1);
+        assert_eq!(key.fee().decode(value), U24::from(3000));
+
+        // slot0 of a pool with a negative tick
+        let tick = Field::<I24>::new(B256::ZERO, 20);
+        let sqrt_price = Field::<U160>::new(B256::ZERO, 0);
+        let value = (U256::from(0xfffffeu32) << 160) | U256::from(42);
+        assert_eq!(tick.decode(value), I24::try_from(-2).unwrap());
+        assert_eq!(sqrt_price.decode(value), U160::from(42));
+        assert!(Field::<bool>::new(B256::ZERO, 31).decode(U256::from(1) << 248));
+    }
+}
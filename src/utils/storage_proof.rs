@@ -0,0 +1,98 @@
+use crate::utils::DexSyncError;
+use alloy::rpc::types::{EIP1186AccountProofResponse, EIP1186StorageProof};
+use alloy_primitives::{Address, B256};
+use reth_primitives::{Account, KECCAK_EMPTY};
+use reth_provider::StateProvider;
+use reth_trie::{AccountProof, StorageProof, TrieInput};
+
+/// Read the account proof and the storage proofs of the slots from the trie tables like `eth_getProof`. The proof is against
+/// the state root of the block of the provider.
+pub fn read_storage_proof<T: StateProvider>(
+    provider: T,
+    address: Address,
+    slots: &[B256],
+) -> Result<EIP1186AccountProofResponse, DexSyncError> {
+    let proof = provider.proof(TrieInput::default(), address, slots)?;
+    let info = proof.info.unwrap_or_default();
+    Ok(EIP1186AccountProofResponse {
+        address,
+        balance: info.balance,
+        code_hash: info.get_bytecode_hash(),
+        nonce: info.nonce,
+        storage_hash: proof.storage_root,
+        account_proof: proof.proof,
+        storage_proof: proof
+            .storage_proofs
+            .into_iter()
+            .map(|storage_proof| EIP1186StorageProof {
+                key: storage_proof.key.into(),
+                value: storage_proof.value,
+                proof: storage_proof.proof,
+            })
+            .collect(),
+    })
+}
+
+/// Verify an `eth_getProof` response against the state root, e.g. the `state_root` of the block header.
+pub fn verify_storage_proof(state_root: B256, response: &EIP1186AccountProofResponse) -> Result<(), DexSyncError> {
+    let exists = response.nonce != 0 || !response.balance.is_zero() || response.code_hash != KECCAK_EMPTY;
+    let proof = AccountProof {
+        address: response.address,
+        info: exists.then_some(Account {
+            nonce: response.nonce,
+            balance: response.balance,
+            bytecode_hash: (response.code_hash != KECCAK_EMPTY).then_some(response.code_hash),
+        }),
+        proof: response.account_proof.clone(),
+        storage_root: response.storage_hash,
+        storage_proofs: response
+            .storage_proof
+            .iter()
+            .map(|storage_proof| StorageProof {
+                value: storage_proof.value,
+                proof: storage_proof.proof.clone(),
+                ..StorageProof::new(storage_proof.key.as_b256())
+            })
+            .collect(),
+    };
+    proof.verify(state_root).map_err(|e| DexSyncError::InvalidProof { address: response.address, reason: e.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, U256};
+    use reth_primitives::StorageEntry;
+    use reth_stages::test_utils::TestStageDB;
+    use reth_trie::StateRoot;
+    use reth_trie_db::DatabaseStateRoot;
+
+    #[test]
+    fn test_storage_proof() -> eyre::Result<()> {
+        let test_db = TestStageDB::default();
+
+        let pair_address = address!("b4e16d0168e52d35cacd2c6185b44281ec28c9dc");
+        let reserve_slot = B256::with_last_byte(8);
+        test_db.insert_accounts_and_storages(vec![
+            (pair_address, (Account { nonce: 1, ..Default::default() }, vec![StorageEntry::new(reserve_slot, U256::from(42))])),
+            (Address::with_last_byte(1), (Account { balance: U256::from(1), ..Default::default() }, vec![])),
+        ])?;
+        let state_root = StateRoot::from_tx(test_db.factory.provider()?.tx_ref()).root()?;
+
+        let response = read_storage_proof(test_db.factory.latest()?, pair_address, &[reserve_slot, B256::with_last_byte(9)])?;
+        assert_eq!(response.storage_proof[0].value, U256::from(42));
+        assert_eq!(response.storage_proof[1].value, U256::ZERO);
+        verify_storage_proof(state_root, &response)?;
+
+        // a tampered reserve must not verify
+        let mut tampered = response.clone();
+        tampered.storage_proof[0].value = U256::from(43);
+        assert!(matches!(verify_storage_proof(state_root, &tampered), Err(DexSyncError::InvalidProof { .. })));
+
+        // proof of an absent account
+        let response = read_storage_proof(test_db.factory.latest()?, Address::with_last_byte(2), &[])?;
+        verify_storage_proof(state_root, &response)?;
+
+        Ok(())
+    }
+}
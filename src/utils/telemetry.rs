@@ -0,0 +1,49 @@
+//! Prometheus metrics of the loaders, only emitted with the `metrics` feature.
+
+use std::time::Duration;
+
+pub const POOLS_LOADED: &str = "dexsync_pools_loaded_total";
+pub const STORAGE_READS: &str = "dexsync_storage_reads_total";
+pub const READ_DURATION: &str = "dexsync_read_duration_seconds";
+pub const CACHE_HITS: &str = "dexsync_cache_hits_total";
+pub const CACHE_MISSES: &str = "dexsync_cache_misses_total";
+
+pub(crate) fn record_pools_loaded(protocol: &'static str, count: usize) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(POOLS_LOADED, "protocol" => protocol).increment(count as u64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (protocol, count);
+}
+
+pub(crate) fn record_storage_reads(count: usize) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(STORAGE_READS).increment(count as u64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = count;
+}
+
+pub(crate) fn record_read_duration(operation: &'static str, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!(READ_DURATION, "operation" => operation).record(duration.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = (operation, duration);
+}
+
+pub(crate) fn record_cache_lookup(hit: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(if hit { CACHE_HITS } else { CACHE_MISSES }).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = hit;
+}
+
+/// Serve all metrics for Prometheus at `http://<addr>/metrics`. Must be called within a tokio runtime.
+#[cfg(feature = "metrics")]
+pub fn install_prometheus_exporter(addr: std::net::SocketAddr) -> eyre::Result<()> {
+    metrics_exporter_prometheus::PrometheusBuilder::new().with_http_listener(addr).install()?;
+    ::metrics::describe_counter!(POOLS_LOADED, "Number of pools loaded");
+    ::metrics::describe_counter!(STORAGE_READS, "Number of storage slots read");
+    ::metrics::describe_histogram!(READ_DURATION, ::metrics::Unit::Seconds, "Duration of a chunk read");
+    ::metrics::describe_counter!(CACHE_HITS, "Number of loads served from the pairs cache");
+    ::metrics::describe_counter!(CACHE_MISSES, "Number of loads without a pairs cache");
+    Ok(())
+}
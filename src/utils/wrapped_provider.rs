@@ -27,6 +27,11 @@ impl WrappedProviderFactory {
     pub fn provider(&self) -> ProviderResult<DatabaseProviderRO<Arc<DatabaseEnv>, ChainSpec>> {
         self.inner.provider()
     }
+
+    /// The wrapped factory, e.g. for the block and receipt readers the wrapper does not implement.
+    pub fn inner(&self) -> &ProviderFactory<NodeTypesWithDBAdapter<EthereumNode, Arc<DatabaseEnv>>> {
+        &self.inner
+    }
 }
 
 impl BlockIdReader for WrappedProviderFactory {
@@ -45,29 +50,29 @@ impl BlockIdReader for WrappedProviderFactory {
 
 impl BlockNumReader for WrappedProviderFactory {
     fn chain_info(&self) -> ProviderResult<ChainInfo> {
-        todo!()
+        self.inner.chain_info()
     }
 
     fn best_block_number(&self) -> ProviderResult<BlockNumber> {
-        todo!()
+        self.inner.best_block_number()
     }
 
     fn last_block_number(&self) -> ProviderResult<BlockNumber> {
-        todo!()
+        self.inner.last_block_number()
     }
 
-    fn block_number(&self, _hash: B256) -> ProviderResult<Option<BlockNumber>> {
-        todo!()
+    fn block_number(&self, hash: B256) -> ProviderResult<Option<BlockNumber>> {
+        self.inner.block_number(hash)
     }
 }
 
 impl BlockHashReader for WrappedProviderFactory {
-    fn block_hash(&self, _number: BlockNumber) -> ProviderResult<Option<B256>> {
-        todo!()
+    fn block_hash(&self, number: BlockNumber) -> ProviderResult<Option<B256>> {
+        self.inner.block_hash(number)
     }
 
-    fn canonical_hashes_range(&self, _start: BlockNumber, _end: BlockNumber) -> ProviderResult<Vec<B256>> {
-        todo!()
+    fn canonical_hashes_range(&self, start: BlockNumber, end: BlockNumber) -> ProviderResult<Vec<B256>> {
+        self.inner.canonical_hashes_range(start, end)
     }
 }
 